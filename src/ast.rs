@@ -0,0 +1,273 @@
+use crate::tokens::{Keyword, Span, Symbol};
+
+/// The root of the typed AST of a Jack class, which mirrors the grammar but
+/// drops the punctuation tokens.
+///
+/// class: 'class' className '{' classVarDec* subroutineDec* '}'
+#[derive(Debug, Clone, PartialEq)]
+pub struct Class {
+    pub name: Identifier,
+    pub var_decs: Vec<ClassVarDec>,
+    pub subroutines: Vec<SubroutineDec>,
+}
+
+/// A class, subroutine or variable name along with where it is used or
+/// declared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identifier {
+    pub name: String,
+    pub span: Span,
+}
+
+/// classVarDec: ('static' | 'field') type varName (',' varName)* ';'
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassVarDec {
+    pub kind: ClassVarKind,
+    pub ty: Type,
+    pub names: Vec<Identifier>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassVarKind {
+    Static,
+    Field,
+}
+
+/// type: 'int' | 'char' | 'boolean' | className
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+    Int,
+    Char,
+    Boolean,
+    Class(String),
+}
+
+impl Type {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Int => Keyword::Int.to_str(),
+            Self::Char => Keyword::Char.to_str(),
+            Self::Boolean => Keyword::Boolean.to_str(),
+            Self::Class(name) => name,
+        }
+    }
+}
+
+/// subroutineDec: ('constructor' | 'function' | 'method') ('void' | type)
+/// subroutineName '(' parameterList ')' subroutineBody
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubroutineDec {
+    pub kind: SubroutineKind,
+    /// The return type, `None` for `void` subroutines.
+    pub return_type: Option<Type>,
+    pub name: Identifier,
+    pub parameters: Vec<Parameter>,
+    pub body: SubroutineBody,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubroutineKind {
+    Constructor,
+    Function,
+    Method,
+}
+
+/// A single `type varName` entry of a parameterList.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub ty: Type,
+    pub name: Identifier,
+}
+
+/// subroutineBody: '{' varDec* statements '}'
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubroutineBody {
+    pub var_decs: Vec<VarDec>,
+    pub statements: Vec<Statement>,
+}
+
+/// varDec: 'var' type varName (',' varName)* ';'
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarDec {
+    pub ty: Type,
+    pub names: Vec<Identifier>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    /// 'let' varName ('[' expression ']')? '=' expression ';'
+    Let {
+        name: Identifier,
+        index: Option<Box<Expression>>,
+        value: Expression,
+        span: Span,
+    },
+    /// 'if' '(' expression ')' '{' statements '}' ('else' '{' statements '}')?
+    If {
+        condition: Expression,
+        statements: Vec<Statement>,
+        else_statements: Option<Vec<Statement>>,
+        span: Span,
+    },
+    /// 'while' '(' expression ')' '{' statements '}'
+    While {
+        condition: Expression,
+        statements: Vec<Statement>,
+        span: Span,
+    },
+    /// 'do' subroutineCall ';'
+    Do(SubroutineCall, Span),
+    /// 'return' expression? ';'
+    Return(Option<Expression>, Span),
+    /// A statement with a syntax error, which the parser skipped
+    Error(Span),
+}
+
+impl Statement {
+    /// Returns the location of the whole statement.
+    pub fn span(&self) -> &Span {
+        match self {
+            Self::Let { span, .. }
+            | Self::If { span, .. }
+            | Self::While { span, .. }
+            | Self::Do(_, span)
+            | Self::Return(_, span)
+            | Self::Error(span) => span,
+        }
+    }
+}
+
+/// expression: term (op term)*
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    pub term: Term,
+    pub ops: Vec<(BinaryOp, Term)>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    IntConst(u16, Span),
+    StringConst(String, Span),
+    KeywordConst(KeywordConst, Span),
+    VarName(Identifier),
+    /// varName '[' expression ']'
+    ArrayAccess(Identifier, Box<Expression>, Span),
+    Call(Box<SubroutineCall>, Span),
+    /// '(' expression ')'
+    Parenthesized(Box<Expression>, Span),
+    Unary(UnaryOp, Box<Term>, Span),
+}
+
+impl Term {
+    /// Returns the location of the whole term.
+    pub fn span(&self) -> &Span {
+        match self {
+            Self::VarName(name) => &name.span,
+            Self::IntConst(_, span)
+            | Self::StringConst(_, span)
+            | Self::KeywordConst(_, span)
+            | Self::ArrayAccess(_, _, span)
+            | Self::Call(_, span)
+            | Self::Parenthesized(_, span)
+            | Self::Unary(_, _, span) => span,
+        }
+    }
+}
+
+/// subroutineName '(' expressionList ')' |
+/// (className | varName) '.' subroutineName '(' expressionList ')'
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubroutineCall {
+    /// The class or variable name before the dot, if any.
+    pub receiver: Option<Identifier>,
+    pub name: Identifier,
+    pub arguments: Vec<Expression>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeywordConst {
+    True,
+    False,
+    Null,
+    This,
+}
+
+impl KeywordConst {
+    pub fn from_keyword(keyword: &Keyword) -> Option<Self> {
+        match keyword {
+            Keyword::True => Some(Self::True),
+            Keyword::False => Some(Self::False),
+            Keyword::Null => Some(Self::Null),
+            Keyword::This => Some(Self::This),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    And,
+    Or,
+    LessThan,
+    MoreThan,
+    Equal,
+}
+
+impl BinaryOp {
+    pub fn from_symbol(symbol: &Symbol) -> Option<Self> {
+        match symbol {
+            Symbol::Plus => Some(Self::Add),
+            Symbol::Minus => Some(Self::Sub),
+            Symbol::Mul => Some(Self::Mul),
+            Symbol::Divide => Some(Self::Div),
+            Symbol::And => Some(Self::And),
+            Symbol::Or => Some(Self::Or),
+            Symbol::LessThan => Some(Self::LessThan),
+            Symbol::MoreThan => Some(Self::MoreThan),
+            Symbol::Equal => Some(Self::Equal),
+            _ => None,
+        }
+    }
+
+    pub fn to_symbol(&self) -> Symbol {
+        match self {
+            Self::Add => Symbol::Plus,
+            Self::Sub => Symbol::Minus,
+            Self::Mul => Symbol::Mul,
+            Self::Div => Symbol::Divide,
+            Self::And => Symbol::And,
+            Self::Or => Symbol::Or,
+            Self::LessThan => Symbol::LessThan,
+            Self::MoreThan => Symbol::MoreThan,
+            Self::Equal => Symbol::Equal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+impl UnaryOp {
+    pub fn from_symbol(symbol: &Symbol) -> Option<Self> {
+        match symbol {
+            Symbol::Minus => Some(Self::Neg),
+            Symbol::Tilte => Some(Self::Not),
+            _ => None,
+        }
+    }
+
+    pub fn to_symbol(&self) -> Symbol {
+        match self {
+            Self::Neg => Symbol::Minus,
+            Self::Not => Symbol::Tilte,
+        }
+    }
+}
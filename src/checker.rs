@@ -0,0 +1,530 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::{
+        Class, Expression, Identifier, KeywordConst, Statement, SubroutineCall, SubroutineDec,
+        SubroutineKind, Term, Type,
+    },
+    errors::SemanticError,
+    os,
+    symbol_table::{Kind, SymbolTable},
+    tokens::Span,
+};
+
+/// The subroutines of the classes of a program, which are compiled
+/// separately but call each other.
+#[derive(Debug, Clone, Default)]
+pub struct Program {
+    classes: HashMap<String, ClassSignature>,
+}
+
+/// A class as seen from the other classes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassSignature {
+    /// The name of the class in its declaration, `None` for the OS classes
+    pub span: Option<Span>,
+    pub subroutines: HashMap<String, SubroutineSignature>,
+}
+
+/// A subroutine as seen from its callers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubroutineSignature {
+    pub kind: SubroutineKind,
+    /// The return type, `None` for `void` subroutines
+    pub return_type: Option<Type>,
+    pub parameters: Vec<Type>,
+    /// The name of the subroutine in its declaration, `None` for the OS
+    /// classes
+    pub span: Option<Span>,
+}
+
+impl Program {
+    /// Collects the signatures of the classes, and of the OS classes that
+    /// they don't declare themselves. The first class wins if several have
+    /// the same name, so that stubs can override the OS declarations.
+    pub fn new<'a>(classes: impl IntoIterator<Item = &'a Class>) -> Self {
+        let mut program = Self::default();
+        for class in classes {
+            program
+                .classes
+                .entry(class.name.name.clone())
+                .or_insert_with(|| ClassSignature::new(class, true));
+        }
+        for class in os::classes() {
+            program
+                .classes
+                .entry(class.name.name.clone())
+                .or_insert_with(|| ClassSignature::new(class, false));
+        }
+        program
+    }
+
+    pub fn class(&self, name: &str) -> Option<&ClassSignature> {
+        self.classes.get(name)
+    }
+
+    /// Whether the name is a class of the program or of the OS.
+    pub fn is_class(&self, name: &str) -> bool {
+        self.classes.contains_key(name)
+    }
+}
+
+impl ClassSignature {
+    /// Collects the signatures of a class, keeping its spans only if they
+    /// point into a source the user can see.
+    fn new(class: &Class, spans: bool) -> Self {
+        let span = |span: &Span| spans.then(|| span.clone());
+        let subroutines = class
+            .subroutines
+            .iter()
+            .map(|subroutine| {
+                let signature = SubroutineSignature {
+                    kind: subroutine.kind,
+                    return_type: subroutine.return_type.clone(),
+                    parameters: subroutine.parameters.iter().map(|p| p.ty.clone()).collect(),
+                    span: span(&subroutine.name.span),
+                };
+                (subroutine.name.name.clone(), signature)
+            })
+            .collect();
+        Self {
+            span: span(&class.name.span),
+            subroutines,
+        }
+    }
+}
+
+/// Checks a class of the program for the errors the parser can't catch,
+/// e.g. undeclared variables or calls to unknown subroutines, and returns
+/// them in the order of the source.
+pub fn check_class(class: &Class, program: &Program) -> Vec<SemanticError> {
+    let mut checker = Checker {
+        class,
+        program,
+        symbol_table: SymbolTable::from_class(class),
+        subroutine_name: String::new(),
+        kind: SubroutineKind::Function,
+        errors: Vec::new(),
+    };
+    let fields = class.var_decs.iter().flat_map(|var_dec| &var_dec.names);
+    checker.check_declarations(fields);
+    for subroutine in &class.subroutines {
+        checker.check_subroutine(subroutine);
+    }
+    checker.errors
+}
+
+/// Walks the AST of a class and collects its semantic errors.
+#[derive(Debug)]
+struct Checker<'a> {
+    /// The class being checked
+    class: &'a Class,
+    /// The classes the class can call
+    program: &'a Program,
+    /// The variables of the class and of the current subroutine
+    symbol_table: SymbolTable,
+    /// The name of the current subroutine, used in error messages
+    subroutine_name: String,
+    /// The kind of the current subroutine
+    kind: SubroutineKind,
+    errors: Vec<SemanticError>,
+}
+
+impl Checker<'_> {
+    fn check_subroutine(&mut self, subroutine: &SubroutineDec) {
+        self.symbol_table.enter_subroutine(subroutine);
+        self.subroutine_name = format!("{}.{}", self.class.name.name, subroutine.name.name);
+        self.kind = subroutine.kind;
+        let parameters = subroutine.parameters.iter().map(|p| &p.name);
+        let locals = subroutine.body.var_decs.iter().flat_map(|v| &v.names);
+        self.check_declarations(parameters.chain(locals));
+        self.check_statements(&subroutine.body.statements);
+    }
+
+    /// Checks that the variables of a scope have distinct names.
+    fn check_declarations<'b>(&mut self, names: impl Iterator<Item = &'b Identifier>) {
+        let mut declared: HashMap<&str, &Span> = HashMap::new();
+        for name in names {
+            match declared.get(name.name.as_str()) {
+                Some(first) => self.errors.push(SemanticError::DuplicateDeclaration {
+                    name: name.name.clone(),
+                    span: name.span.clone(),
+                    first: (*first).clone(),
+                }),
+                None => {
+                    declared.insert(&name.name, &name.span);
+                }
+            }
+        }
+    }
+
+    fn check_statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.check_statement(statement);
+        }
+    }
+
+    fn check_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Let {
+                name, index, value, ..
+            } => {
+                self.check_variable(name);
+                if let Some(index) = index {
+                    self.check_expression(index);
+                }
+                self.check_expression(value);
+            }
+            Statement::If {
+                condition,
+                statements,
+                else_statements,
+                ..
+            } => {
+                self.check_expression(condition);
+                self.check_statements(statements);
+                if let Some(else_statements) = else_statements {
+                    self.check_statements(else_statements);
+                }
+            }
+            Statement::While {
+                condition,
+                statements,
+                ..
+            } => {
+                self.check_expression(condition);
+                self.check_statements(statements);
+            }
+            Statement::Do(call, _) => self.check_call(call),
+            Statement::Return(value, _) => {
+                if let Some(value) = value {
+                    self.check_expression(value);
+                }
+            }
+            Statement::Error(_) => {}
+        }
+    }
+
+    fn check_expression(&mut self, expression: &Expression) {
+        self.check_term(&expression.term);
+        for (_, term) in &expression.ops {
+            self.check_term(term);
+        }
+    }
+
+    fn check_term(&mut self, term: &Term) {
+        match term {
+            Term::IntConst(..) | Term::StringConst(..) => {}
+            Term::KeywordConst(KeywordConst::This, span) => {
+                if self.kind == SubroutineKind::Function {
+                    self.errors.push(SemanticError::ThisInFunction {
+                        subroutine: self.subroutine_name.clone(),
+                        span: span.clone(),
+                    });
+                }
+            }
+            Term::KeywordConst(..) => {}
+            Term::VarName(name) => self.check_variable(name),
+            Term::ArrayAccess(name, index, _) => {
+                self.check_variable(name);
+                self.check_expression(index);
+            }
+            Term::Call(call, _) => self.check_call(call),
+            Term::Parenthesized(expression, _) => self.check_expression(expression),
+            Term::Unary(_, term, _) => self.check_term(term),
+        }
+    }
+
+    fn check_variable(&mut self, name: &Identifier) {
+        match self.symbol_table.kind_of(&name.name) {
+            None => self.errors.push(SemanticError::UndeclaredVariable {
+                name: name.name.clone(),
+                span: name.span.clone(),
+            }),
+            Some(Kind::Field) if self.kind == SubroutineKind::Function => {
+                self.errors.push(SemanticError::FieldInFunction {
+                    name: name.name.clone(),
+                    subroutine: self.subroutine_name.clone(),
+                    span: name.span.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+
+    /// Checks the called subroutine, then the arguments, which follow it in
+    /// the source.
+    fn check_call(&mut self, call: &SubroutineCall) {
+        self.check_callee(call);
+        for argument in &call.arguments {
+            self.check_expression(argument);
+        }
+    }
+
+    /// Checks that the called subroutine exists on the class of the receiver
+    /// and accepts the arguments. The calls on the variables of a primitive
+    /// type aren't checked.
+    fn check_callee(&mut self, call: &SubroutineCall) {
+        // Whether the call has an object, explicit or the current one
+        let (class_name, has_object) = match &call.receiver {
+            None => (
+                self.class.name.name.clone(),
+                self.kind != SubroutineKind::Function,
+            ),
+            Some(receiver) => match self.symbol_table.lookup(&receiver.name) {
+                Some(variable) => (variable.ty.name().to_string(), true),
+                None if self.program.is_class(&receiver.name) => (receiver.name.clone(), false),
+                None => {
+                    self.errors.push(unknown_receiver(receiver));
+                    return;
+                }
+            },
+        };
+        let Some(class) = self.program.class(&class_name) else {
+            return;
+        };
+        let Some(subroutine) = class.subroutines.get(&call.name.name) else {
+            self.errors.push(SemanticError::UnknownSubroutine {
+                class: class_name,
+                name: call.name.name.clone(),
+                span: call.name.span.clone(),
+                class_span: class.span.clone(),
+            });
+            return;
+        };
+
+        let full_name = format!("{}.{}", class_name, call.name.name);
+        if subroutine.kind == SubroutineKind::Method && !has_object {
+            self.errors.push(SemanticError::MethodCalledAsFunction {
+                subroutine: full_name.clone(),
+                span: call.name.span.clone(),
+                declaration: subroutine.span.clone(),
+            });
+        }
+        if subroutine.parameters.len() != call.arguments.len() {
+            self.errors.push(SemanticError::ArgumentCount {
+                subroutine: full_name,
+                expected: subroutine.parameters.len(),
+                found: call.arguments.len(),
+                span: call.name.span.clone(),
+                declaration: subroutine.span.clone(),
+            });
+        }
+    }
+}
+
+/// Returns the error for a receiver that is neither a variable nor a class.
+/// Class names are capitalized in Jack, so a capitalized receiver is likely
+/// a misspelled class and any other one a misspelled variable.
+fn unknown_receiver(receiver: &Identifier) -> SemanticError {
+    match receiver.name.starts_with(|c: char| c.is_ascii_uppercase()) {
+        true => SemanticError::UnknownClass {
+            name: receiver.name.clone(),
+            span: receiver.span.clone(),
+        },
+        false => SemanticError::UndeclaredVariable {
+            name: receiver.name.clone(),
+            span: receiver.span.clone(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::check_sources;
+
+    #[test]
+    fn test_check_duplicate_declarations() {
+        // Given
+        let source = "class Main {
+  field int x; static boolean x;
+  function void main(int a) { var int x; var boolean x, a; return; }
+}";
+
+        // When
+        let errors = check_sources(&[source], check_class);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            errors,
+            vec![
+                (
+                    2,
+                    31,
+                    String::from("`x` is declared twice in the same scope")
+                ),
+                (
+                    3,
+                    54,
+                    String::from("`x` is declared twice in the same scope")
+                ),
+                (
+                    3,
+                    57,
+                    String::from("`a` is declared twice in the same scope")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_variables() {
+        // Given
+        let source = "class Main {
+  field int x;
+  method void f(int a) { var int b; let b = a + x + c; let d[b] = e.g(); return; }
+}";
+
+        // When
+        let errors = check_sources(&[source], check_class);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            errors,
+            vec![
+                (3, 53, String::from("undeclared variable `c`")),
+                (3, 60, String::from("undeclared variable `d`")),
+                (3, 67, String::from("undeclared variable `e`")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_calls() {
+        // Given
+        let main = "class Main {
+  function void main() {
+    var Game game;
+    let game = Game.new(1);
+    do game.start(1, 2);
+    do game.stop();
+    do Gme.new();
+    do Game.run();
+    do Output.printInt(Math.whatever());
+    return;
+  }
+}";
+        let game = "class Game {
+  constructor Game new() { return this; }
+  method void start(int speed) { return; }
+  method void run() { return; }
+}";
+
+        // When
+        let errors = check_sources(&[main, game], check_class);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            errors,
+            vec![
+                (
+                    4,
+                    21,
+                    String::from("`Game.new` takes 0 arguments but 1 was given")
+                ),
+                (
+                    5,
+                    13,
+                    String::from("`Game.start` takes 1 argument but 2 were given")
+                ),
+                (6, 13, String::from("no subroutine `stop` in class `Game`")),
+                (7, 8, String::from("unknown class `Gme`")),
+                (
+                    8,
+                    13,
+                    String::from("method `Game.run` called without an object")
+                ),
+                (
+                    9,
+                    29,
+                    String::from("no subroutine `whatever` in class `Math`")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_os_calls() {
+        // Given
+        let source = r#"class Main {
+  function void main() {
+    var String s;
+    let s = String.new(Keyboard.readInt("length?"));
+    do s.appendChar(String.newLine());
+    do Output.printInt(s.length(), 10);
+    do Output.printString(String.length());
+    do Sys.halt();
+    return;
+  }
+}"#;
+        let stub = "class Output {
+  function void printInt(int i, int base) {}
+}";
+
+        // When
+        let errors = check_sources(&[source], check_class);
+        let overridden = check_sources(&[source, stub], check_class);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            errors,
+            vec![
+                (
+                    6,
+                    15,
+                    String::from("`Output.printInt` takes 1 argument but 2 were given")
+                ),
+                (
+                    7,
+                    34,
+                    String::from("method `String.length` called without an object")
+                ),
+            ]
+        );
+        pretty_assertions::assert_eq!(
+            overridden,
+            vec![
+                (
+                    7,
+                    15,
+                    String::from("no subroutine `printString` in class `Output`")
+                ),
+                (
+                    7,
+                    34,
+                    String::from("method `String.length` called without an object")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_functions() {
+        // Given
+        let source = "class Main {
+  field int x;
+  method void draw() { do draw(); return; }
+  function Main make() { do draw(); return this; }
+  function int f() { let x = 2; return x; }
+}";
+
+        // When
+        let errors = check_sources(&[source], check_class);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            errors,
+            vec![
+                (
+                    4,
+                    29,
+                    String::from("method `Main.draw` called without an object")
+                ),
+                (4, 44, String::from("`this` used in function `Main.make`")),
+                (5, 26, String::from("field `x` used in function `Main.f`")),
+                (5, 40, String::from("field `x` used in function `Main.f`")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,513 @@
+use crate::{
+    ast::{
+        BinaryOp, Class, Expression, Identifier, KeywordConst, Statement, SubroutineCall,
+        SubroutineDec, SubroutineKind, Term, UnaryOp,
+    },
+    errors::CodeGenError,
+    symbol_table::{Kind, SymbolEntry, SymbolTable},
+    vm_writer::{Command, Segment, VMWriter},
+};
+
+/// Compiles a parsed Jack class into Hack VM code. A subroutine stops being
+/// compiled at its first error, and the errors of all the subroutines are
+/// returned.
+pub fn compile_class(class: &Class) -> Result<String, Vec<CodeGenError>> {
+    let mut generator = CodeGenerator::new(class);
+    let errors = generator.compile_class();
+    match errors.is_empty() {
+        true => Ok(generator.writer.into_output()),
+        false => Err(errors),
+    }
+}
+
+/// Walks the AST of a class and writes the matching VM commands.
+#[derive(Debug)]
+struct CodeGenerator<'a> {
+    /// The class being compiled
+    class: &'a Class,
+    /// The writer receiving the VM commands
+    writer: VMWriter,
+    /// The variables of the class and of the current subroutine
+    symbol_table: SymbolTable,
+    /// The name of the subroutine being compiled, used in error messages
+    subroutine_name: String,
+    /// The counter used to generate unique labels within a subroutine
+    label_count: usize,
+}
+
+impl<'a> CodeGenerator<'a> {
+    fn new(class: &'a Class) -> Self {
+        Self {
+            class,
+            writer: VMWriter::new(),
+            symbol_table: SymbolTable::from_class(class),
+            subroutine_name: String::new(),
+            label_count: 0,
+        }
+    }
+
+    fn compile_class(&mut self) -> Vec<CodeGenError> {
+        self.class
+            .subroutines
+            .iter()
+            .filter_map(|subroutine| self.compile_subroutine(subroutine).err())
+            .collect()
+    }
+
+    fn compile_subroutine(&mut self, subroutine: &SubroutineDec) -> Result<(), CodeGenError> {
+        self.symbol_table.enter_subroutine(subroutine);
+        self.subroutine_name = subroutine.name.name.clone();
+        self.label_count = 0;
+
+        self.writer.write_function(
+            &format!("{}.{}", self.class.name.name, subroutine.name.name),
+            self.symbol_table.var_count(Kind::Var) as usize,
+        );
+        match subroutine.kind {
+            SubroutineKind::Constructor => {
+                let n_fields = self.symbol_table.var_count(Kind::Field);
+                self.writer.write_push(Segment::Constant, n_fields);
+                self.writer.write_call("Memory.alloc", 1);
+                self.writer.write_pop(Segment::Pointer, 0);
+            }
+            SubroutineKind::Method => {
+                self.writer.write_push(Segment::Argument, 0);
+                self.writer.write_pop(Segment::Pointer, 0);
+            }
+            SubroutineKind::Function => {}
+        }
+        self.compile_statements(&subroutine.body.statements)
+    }
+
+    fn compile_statements(&mut self, statements: &[Statement]) -> Result<(), CodeGenError> {
+        for statement in statements {
+            self.compile_statement(statement)?;
+        }
+        Ok(())
+    }
+
+    fn compile_statement(&mut self, statement: &Statement) -> Result<(), CodeGenError> {
+        match statement {
+            Statement::Let {
+                name, index, value, ..
+            } => {
+                let variable = self.variable(name)?;
+                match index {
+                    Some(index) => {
+                        self.writer
+                            .write_push(segment(variable.kind), variable.index);
+                        self.compile_expression(index)?;
+                        self.writer.write_arithmetic(Command::Add);
+                        self.compile_expression(value)?;
+                        self.writer.write_pop(Segment::Temp, 0);
+                        self.writer.write_pop(Segment::Pointer, 1);
+                        self.writer.write_push(Segment::Temp, 0);
+                        self.writer.write_pop(Segment::That, 0);
+                    }
+                    None => {
+                        self.compile_expression(value)?;
+                        self.writer
+                            .write_pop(segment(variable.kind), variable.index);
+                    }
+                }
+            }
+            Statement::If {
+                condition,
+                statements,
+                else_statements,
+                ..
+            } => {
+                let label_index = self.next_label_index();
+                let else_label = format!("IF_ELSE{}", label_index);
+                let end_label = format!("IF_END{}", label_index);
+
+                self.compile_expression(condition)?;
+                self.writer.write_arithmetic(Command::Not);
+                self.writer.write_if(&else_label);
+                self.compile_statements(statements)?;
+                self.writer.write_goto(&end_label);
+                self.writer.write_label(&else_label);
+                if let Some(else_statements) = else_statements {
+                    self.compile_statements(else_statements)?;
+                }
+                self.writer.write_label(&end_label);
+            }
+            Statement::While {
+                condition,
+                statements,
+                ..
+            } => {
+                let label_index = self.next_label_index();
+                let start_label = format!("WHILE_EXP{}", label_index);
+                let end_label = format!("WHILE_END{}", label_index);
+
+                self.writer.write_label(&start_label);
+                self.compile_expression(condition)?;
+                self.writer.write_arithmetic(Command::Not);
+                self.writer.write_if(&end_label);
+                self.compile_statements(statements)?;
+                self.writer.write_goto(&start_label);
+                self.writer.write_label(&end_label);
+            }
+            Statement::Do(call, _) => {
+                self.compile_subroutine_call(call)?;
+                // Discard the returned value
+                self.writer.write_pop(Segment::Temp, 0);
+            }
+            Statement::Return(value, _) => {
+                match value {
+                    Some(value) => self.compile_expression(value)?,
+                    None => self.writer.write_push(Segment::Constant, 0),
+                }
+                self.writer.write_return();
+            }
+            // Only parsed along with a syntax error, which stops the compilation
+            Statement::Error(_) => {}
+        }
+        Ok(())
+    }
+
+    fn compile_expression(&mut self, expression: &Expression) -> Result<(), CodeGenError> {
+        self.compile_term(&expression.term)?;
+        for (op, term) in &expression.ops {
+            self.compile_term(term)?;
+            match op {
+                BinaryOp::Add => self.writer.write_arithmetic(Command::Add),
+                BinaryOp::Sub => self.writer.write_arithmetic(Command::Sub),
+                BinaryOp::Mul => self.writer.write_call("Math.multiply", 2),
+                BinaryOp::Div => self.writer.write_call("Math.divide", 2),
+                BinaryOp::And => self.writer.write_arithmetic(Command::And),
+                BinaryOp::Or => self.writer.write_arithmetic(Command::Or),
+                BinaryOp::LessThan => self.writer.write_arithmetic(Command::Lt),
+                BinaryOp::MoreThan => self.writer.write_arithmetic(Command::Gt),
+                BinaryOp::Equal => self.writer.write_arithmetic(Command::Eq),
+            }
+        }
+        Ok(())
+    }
+
+    fn compile_term(&mut self, term: &Term) -> Result<(), CodeGenError> {
+        match term {
+            Term::IntConst(i, _) => self.writer.write_push(Segment::Constant, *i),
+            Term::StringConst(s, _) => {
+                self.writer
+                    .write_push(Segment::Constant, s.chars().count() as u16);
+                self.writer.write_call("String.new", 1);
+                for c in s.chars() {
+                    self.writer.write_push(Segment::Constant, c as u16);
+                    self.writer.write_call("String.appendChar", 2);
+                }
+            }
+            Term::KeywordConst(KeywordConst::True, _) => {
+                self.writer.write_push(Segment::Constant, 0);
+                self.writer.write_arithmetic(Command::Not);
+            }
+            Term::KeywordConst(KeywordConst::False | KeywordConst::Null, _) => {
+                self.writer.write_push(Segment::Constant, 0)
+            }
+            Term::KeywordConst(KeywordConst::This, _) => {
+                self.writer.write_push(Segment::Pointer, 0)
+            }
+            Term::VarName(name) => {
+                let variable = self.variable(name)?;
+                self.writer
+                    .write_push(segment(variable.kind), variable.index);
+            }
+            Term::ArrayAccess(name, index, _) => {
+                let variable = self.variable(name)?;
+                self.writer
+                    .write_push(segment(variable.kind), variable.index);
+                self.compile_expression(index)?;
+                self.writer.write_arithmetic(Command::Add);
+                self.writer.write_pop(Segment::Pointer, 1);
+                self.writer.write_push(Segment::That, 0);
+            }
+            Term::Call(call, _) => self.compile_subroutine_call(call)?,
+            Term::Parenthesized(expression, _) => self.compile_expression(expression)?,
+            Term::Unary(op, term, _) => {
+                self.compile_term(term)?;
+                match op {
+                    UnaryOp::Neg => self.writer.write_arithmetic(Command::Neg),
+                    UnaryOp::Not => self.writer.write_arithmetic(Command::Not),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Pushes the receiving object if any and the arguments, then calls the subroutine.
+    /// A call is one of:
+    /// - `foo(...)`: a method of the current object.
+    /// - `bar.foo(...)` with `bar` a variable: a method of the object in `bar`.
+    /// - `Bar.foo(...)`: a function or constructor of the class `Bar`.
+    fn compile_subroutine_call(&mut self, call: &SubroutineCall) -> Result<(), CodeGenError> {
+        let (class_name, n_receivers) = match &call.receiver {
+            None => {
+                self.writer.write_push(Segment::Pointer, 0);
+                (self.class.name.name.clone(), 1)
+            }
+            Some(receiver) => match self.lookup(&receiver.name) {
+                Some(variable) => {
+                    self.writer
+                        .write_push(segment(variable.kind), variable.index);
+                    (variable.ty.name().to_string(), 1)
+                }
+                None => (receiver.name.clone(), 0),
+            },
+        };
+        for argument in &call.arguments {
+            self.compile_expression(argument)?;
+        }
+        self.writer.write_call(
+            &format!("{}.{}", class_name, call.name.name),
+            call.arguments.len() + n_receivers,
+        );
+        Ok(())
+    }
+
+    fn next_label_index(&mut self) -> usize {
+        self.label_count += 1;
+        self.label_count - 1
+    }
+
+    fn lookup(&self, name: &str) -> Option<SymbolEntry> {
+        self.symbol_table.lookup(name).cloned()
+    }
+
+    fn variable(&self, name: &Identifier) -> Result<SymbolEntry, CodeGenError> {
+        self.lookup(&name.name)
+            .ok_or_else(|| CodeGenError::UndeclaredVariable {
+                name: name.name.clone(),
+                subroutine: format!("{}.{}", self.class.name.name, self.subroutine_name),
+                span: name.span.clone(),
+            })
+    }
+}
+
+/// Returns the VM segment storing the variables of the given kind.
+fn segment(kind: Kind) -> Segment {
+    match kind {
+        Kind::Static => Segment::Static,
+        Kind::Field => Segment::This,
+        Kind::Arg => Segment::Argument,
+        Kind::Var => Segment::Local,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::parse_class, tokens::Span};
+
+    fn compile(source: &str) -> String {
+        compile_class(&parse_class(source).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_compile_seven() {
+        // Given
+        let input = r"
+        class Main {
+            function void main() {
+                do Output.printInt(1 + (2 * 3));
+                return;
+            }
+        }";
+
+        // When
+        let output = compile(input);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            output,
+            "function Main.main 0
+push constant 1
+push constant 2
+push constant 3
+call Math.multiply 2
+add
+call Output.printInt 1
+pop temp 0
+push constant 0
+return
+"
+        );
+    }
+
+    #[test]
+    fn test_compile_objects() {
+        // Given
+        let input = r"
+        class Counter {
+            field int count;
+            static Array history;
+
+            constructor Counter new() {
+                let count = 0;
+                return this;
+            }
+
+            method void incr(int by) {
+                let history[count] = by;
+                let count = count + by;
+                do log(count);
+                return;
+            }
+
+            method void log(int value) {
+                var Counter other;
+                do other.incr(value);
+                return;
+            }
+        }";
+
+        // When
+        let output = compile(input);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            output,
+            "function Counter.new 0
+push constant 1
+call Memory.alloc 1
+pop pointer 0
+push constant 0
+pop this 0
+push pointer 0
+return
+function Counter.incr 0
+push argument 0
+pop pointer 0
+push static 0
+push this 0
+add
+push argument 1
+pop temp 0
+pop pointer 1
+push temp 0
+pop that 0
+push this 0
+push argument 1
+add
+pop this 0
+push pointer 0
+push this 0
+call Counter.log 2
+pop temp 0
+push constant 0
+return
+function Counter.log 1
+push argument 0
+pop pointer 0
+push local 0
+push argument 1
+call Counter.incr 2
+pop temp 0
+push constant 0
+return
+"
+        );
+    }
+
+    #[test]
+    fn test_compile_control_flow() {
+        // Given
+        let input = r#"
+        class Main {
+            function int loop(boolean flag) {
+                var Array a;
+                while (~flag) {
+                    if (a[0] = -1) { let flag = true; } else { let a = "ab"; }
+                }
+                return a[1];
+            }
+        }"#;
+
+        // When
+        let output = compile(input);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            output,
+            "function Main.loop 1
+label WHILE_EXP0
+push argument 0
+not
+not
+if-goto WHILE_END0
+push local 0
+push constant 0
+add
+pop pointer 1
+push that 0
+push constant 1
+neg
+eq
+not
+if-goto IF_ELSE1
+push constant 0
+not
+pop argument 0
+goto IF_END1
+label IF_ELSE1
+push constant 2
+call String.new 1
+push constant 97
+call String.appendChar 2
+push constant 98
+call String.appendChar 2
+pop local 0
+label IF_END1
+goto WHILE_EXP0
+label WHILE_END0
+push local 0
+push constant 1
+add
+pop pointer 1
+push that 0
+return
+"
+        );
+    }
+
+    #[test]
+    fn test_compile_undeclared_variable() {
+        // Given
+        let class = parse_class(
+            "class Main { function void main() { let x = 1; let y = 2; return; } method void f() { return z; } }",
+        )
+        .unwrap();
+
+        // When
+        let errors = compile_class(&class).unwrap_err();
+
+        // Then
+        assert_eq!(
+            errors,
+            vec![
+                CodeGenError::UndeclaredVariable {
+                    name: String::from("x"),
+                    subroutine: String::from("Main.main"),
+                    span: Span {
+                        file: None,
+                        start: 40,
+                        end: 41,
+                        line: 1,
+                        column: 41,
+                    },
+                },
+                CodeGenError::UndeclaredVariable {
+                    name: String::from("z"),
+                    subroutine: String::from("Main.f"),
+                    span: Span {
+                        file: None,
+                        start: 93,
+                        end: 94,
+                        line: 1,
+                        column: 94,
+                    },
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,663 @@
+use crate::{
+    ast::Type,
+    cst::{Cst, CstElement, CstNode, CstToken},
+    errors::ParseError,
+    symbol_table::{Kind, SymbolTable},
+    tokenizer::{one_of, JackTokenizer},
+    tokens::{Keyword, Symbol, Token},
+};
+
+/// What an identifier names, written in the extended XML output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Category {
+    Class,
+    Subroutine,
+    Variable(Kind),
+}
+
+impl Category {
+    fn to_str(self) -> &'static str {
+        match self {
+            Self::Class => "class",
+            Self::Subroutine => "subroutine",
+            Self::Variable(kind) => kind.to_str(),
+        }
+    }
+}
+
+/// Recursive descent parser over the tokens of a single Jack class.
+/// Each `compile_*` method handles one rule of the Jack grammar and
+/// adds the matching node to the concrete syntax tree.
+#[derive(Debug)]
+pub struct CompilationEngine {
+    /// The tokenizer providing the input tokens
+    tokenizer: JackTokenizer,
+    /// The nodes being built, from the root to the current one
+    stack: Vec<CstNode>,
+    /// The variables declared so far in the class and current subroutine
+    symbol_table: SymbolTable,
+    /// Whether identifiers carry their category, index and usage
+    extended: bool,
+}
+
+impl CompilationEngine {
+    pub fn new(tokenizer: JackTokenizer) -> Self {
+        Self {
+            tokenizer,
+            stack: Vec::new(),
+            symbol_table: SymbolTable::default(),
+            extended: false,
+        }
+    }
+
+    /// Creates an engine writing the extended XML, where each identifier
+    /// element carries its category, running index and whether it is being
+    /// declared or used, e.g.:
+    /// `<identifier category="field" index="0" usage="declared"> x </identifier>`
+    pub fn extended(tokenizer: JackTokenizer) -> Self {
+        Self {
+            extended: true,
+            ..Self::new(tokenizer)
+        }
+    }
+
+    /// Compiles the class and returns the parse tree XML.
+    pub fn compile(self) -> Result<String, ParseError> {
+        Ok(self.compile_tree()?.to_xml())
+    }
+
+    /// Compiles the class and returns its concrete syntax tree, which keeps
+    /// the whitespace and comments if the tokenizer is lossless. Fails on
+    /// the first syntax error, or on tokens following the class.
+    pub fn compile_tree(mut self) -> Result<Cst, ParseError> {
+        self.compile_class()?;
+        if self.tokenizer.has_more_tokens() {
+            return Err(self.tokenizer.unexpected("end of input"));
+        }
+        let root = match self.stack.pop().and_then(|mut node| node.children.pop()) {
+            Some(CstElement::Node(root)) => root,
+            _ => unreachable!("compile_class leaves the class node"),
+        };
+        Ok(Cst {
+            root,
+            trailing_trivia: self.tokenizer.leading_trivia().to_vec(),
+        })
+    }
+
+    /// class: 'class' className '{' classVarDec* subroutineDec* '}'
+    pub fn compile_class(&mut self) -> Result<(), ParseError> {
+        // The class node is added to an unnamed root
+        self.stack = vec![CstNode::new("")];
+        self.open("class");
+        self.eat_keyword(&[Keyword::Class])?;
+        let name = self.current_identifier()?;
+        self.symbol_table = SymbolTable::new(&name);
+        self.eat_identifier(Category::Class, true)?;
+        self.eat_symbol(Symbol::CurlLeft)?;
+        while self.is_keyword(&[Keyword::Static, Keyword::Field]) {
+            self.compile_class_var_dec()?;
+        }
+        while self.is_keyword(&[Keyword::Constructor, Keyword::Function, Keyword::Method]) {
+            self.compile_subroutine()?;
+        }
+        self.eat_symbol(Symbol::CurlRight)?;
+        self.close("class");
+        Ok(())
+    }
+
+    /// classVarDec: ('static' | 'field') type varName (',' varName)* ';'
+    pub fn compile_class_var_dec(&mut self) -> Result<(), ParseError> {
+        self.open("classVarDec");
+        let keyword = self.eat_keyword(&[Keyword::Static, Keyword::Field])?;
+        let kind = Kind::from_keyword(&keyword).expect("static and field are variable kinds");
+        let ty = self.compile_type()?;
+        self.compile_var_names(kind, ty)?;
+        self.close("classVarDec");
+        Ok(())
+    }
+
+    /// subroutineDec: ('constructor' | 'function' | 'method') ('void' | type)
+    /// subroutineName '(' parameterList ')' subroutineBody
+    pub fn compile_subroutine(&mut self) -> Result<(), ParseError> {
+        self.open("subroutineDec");
+        self.symbol_table.start_subroutine();
+        if self.is_keyword(&[Keyword::Method]) {
+            let ty = Type::Class(self.symbol_table.class_name().to_string());
+            self.symbol_table
+                .define(Keyword::This.to_str(), ty, Kind::Arg);
+        }
+        self.eat_keyword(&[Keyword::Constructor, Keyword::Function, Keyword::Method])?;
+        if self.is_keyword(&[Keyword::Void]) {
+            self.eat_keyword(&[Keyword::Void])?;
+        } else {
+            self.compile_type()?;
+        }
+        self.eat_identifier(Category::Subroutine, true)?;
+        self.eat_symbol(Symbol::ParenthesisLeft)?;
+        self.compile_parameter_list()?;
+        self.eat_symbol(Symbol::ParenthesisRight)?;
+        self.compile_subroutine_body()?;
+        self.close("subroutineDec");
+        Ok(())
+    }
+
+    /// parameterList: ((type varName) (',' type varName)*)?
+    pub fn compile_parameter_list(&mut self) -> Result<(), ParseError> {
+        self.open("parameterList");
+        if !self.is_symbol(Symbol::ParenthesisRight) {
+            let ty = self.compile_type()?;
+            self.eat_declaration(Kind::Arg, ty)?;
+            while self.is_symbol(Symbol::Comma) {
+                self.eat_symbol(Symbol::Comma)?;
+                let ty = self.compile_type()?;
+                self.eat_declaration(Kind::Arg, ty)?;
+            }
+        }
+        self.close("parameterList");
+        Ok(())
+    }
+
+    /// subroutineBody: '{' varDec* statements '}'
+    pub fn compile_subroutine_body(&mut self) -> Result<(), ParseError> {
+        self.open("subroutineBody");
+        self.eat_symbol(Symbol::CurlLeft)?;
+        while self.is_keyword(&[Keyword::Var]) {
+            self.compile_var_dec()?;
+        }
+        self.compile_statements()?;
+        self.eat_symbol(Symbol::CurlRight)?;
+        self.close("subroutineBody");
+        Ok(())
+    }
+
+    /// varDec: 'var' type varName (',' varName)* ';'
+    pub fn compile_var_dec(&mut self) -> Result<(), ParseError> {
+        self.open("varDec");
+        self.eat_keyword(&[Keyword::Var])?;
+        let ty = self.compile_type()?;
+        self.compile_var_names(Kind::Var, ty)?;
+        self.close("varDec");
+        Ok(())
+    }
+
+    /// statements: statement*
+    pub fn compile_statements(&mut self) -> Result<(), ParseError> {
+        self.open("statements");
+        loop {
+            match self.current_keyword() {
+                Some(Keyword::Let) => self.compile_let()?,
+                Some(Keyword::If) => self.compile_if()?,
+                Some(Keyword::While) => self.compile_while()?,
+                Some(Keyword::Do) => self.compile_do()?,
+                Some(Keyword::Return) => self.compile_return()?,
+                _ => break,
+            }
+        }
+        self.close("statements");
+        Ok(())
+    }
+
+    /// letStatement: 'let' varName ('[' expression ']')? '=' expression ';'
+    pub fn compile_let(&mut self) -> Result<(), ParseError> {
+        self.open("letStatement");
+        self.eat_keyword(&[Keyword::Let])?;
+        self.eat_variable()?;
+        if self.is_symbol(Symbol::SquareBracketLeft) {
+            self.eat_symbol(Symbol::SquareBracketLeft)?;
+            self.compile_expression()?;
+            self.eat_symbol(Symbol::SquareBracketRight)?;
+        }
+        self.eat_symbol(Symbol::Equal)?;
+        self.compile_expression()?;
+        self.eat_symbol(Symbol::Semicolon)?;
+        self.close("letStatement");
+        Ok(())
+    }
+
+    /// ifStatement: 'if' '(' expression ')' '{' statements '}'
+    /// ('else' '{' statements '}')?
+    pub fn compile_if(&mut self) -> Result<(), ParseError> {
+        self.open("ifStatement");
+        self.eat_keyword(&[Keyword::If])?;
+        self.compile_condition_and_body()?;
+        if self.is_keyword(&[Keyword::Else]) {
+            self.eat_keyword(&[Keyword::Else])?;
+            self.eat_symbol(Symbol::CurlLeft)?;
+            self.compile_statements()?;
+            self.eat_symbol(Symbol::CurlRight)?;
+        }
+        self.close("ifStatement");
+        Ok(())
+    }
+
+    /// whileStatement: 'while' '(' expression ')' '{' statements '}'
+    pub fn compile_while(&mut self) -> Result<(), ParseError> {
+        self.open("whileStatement");
+        self.eat_keyword(&[Keyword::While])?;
+        self.compile_condition_and_body()?;
+        self.close("whileStatement");
+        Ok(())
+    }
+
+    /// doStatement: 'do' subroutineCall ';'
+    pub fn compile_do(&mut self) -> Result<(), ParseError> {
+        self.open("doStatement");
+        self.eat_keyword(&[Keyword::Do])?;
+        self.compile_subroutine_call()?;
+        self.eat_symbol(Symbol::Semicolon)?;
+        self.close("doStatement");
+        Ok(())
+    }
+
+    /// returnStatement: 'return' expression? ';'
+    pub fn compile_return(&mut self) -> Result<(), ParseError> {
+        self.open("returnStatement");
+        self.eat_keyword(&[Keyword::Return])?;
+        if !self.is_symbol(Symbol::Semicolon) {
+            self.compile_expression()?;
+        }
+        self.eat_symbol(Symbol::Semicolon)?;
+        self.close("returnStatement");
+        Ok(())
+    }
+
+    /// expression: term (op term)*
+    pub fn compile_expression(&mut self) -> Result<(), ParseError> {
+        self.open("expression");
+        self.compile_term()?;
+        while self.is_op() {
+            self.write_current_token()?;
+            self.compile_term()?;
+        }
+        self.close("expression");
+        Ok(())
+    }
+
+    /// term: integerConstant | stringConstant | keywordConstant | varName |
+    /// varName '[' expression ']' | subroutineCall | '(' expression ')' | unaryOp term
+    pub fn compile_term(&mut self) -> Result<(), ParseError> {
+        self.open("term");
+        match self.tokenizer.peek(0) {
+            Some(Token::IntConst(_) | Token::StringConst(_)) => self.write_current_token()?,
+            Some(Token::Keyword(
+                Keyword::True | Keyword::False | Keyword::Null | Keyword::This,
+            )) => self.write_current_token()?,
+            Some(Token::Symbol(Symbol::ParenthesisLeft)) => {
+                self.eat_symbol(Symbol::ParenthesisLeft)?;
+                self.compile_expression()?;
+                self.eat_symbol(Symbol::ParenthesisRight)?;
+            }
+            Some(Token::Symbol(Symbol::Minus | Symbol::Tilte)) => {
+                self.write_current_token()?;
+                self.compile_term()?;
+            }
+            Some(Token::Identifier(_)) => match self.tokenizer.peek(1) {
+                Some(Token::Symbol(Symbol::ParenthesisLeft | Symbol::Dot)) => {
+                    self.compile_subroutine_call()?
+                }
+                _ => {
+                    self.eat_variable()?;
+                    if self.is_symbol(Symbol::SquareBracketLeft) {
+                        self.eat_symbol(Symbol::SquareBracketLeft)?;
+                        self.compile_expression()?;
+                        self.eat_symbol(Symbol::SquareBracketRight)?;
+                    }
+                }
+            },
+            _ => return Err(self.tokenizer.unexpected("a term")),
+        }
+        self.close("term");
+        Ok(())
+    }
+
+    /// expressionList: (expression (',' expression)*)?
+    pub fn compile_expression_list(&mut self) -> Result<(), ParseError> {
+        self.open("expressionList");
+        if !self.is_symbol(Symbol::ParenthesisRight) {
+            self.compile_expression()?;
+            while self.is_symbol(Symbol::Comma) {
+                self.eat_symbol(Symbol::Comma)?;
+                self.compile_expression()?;
+            }
+        }
+        self.close("expressionList");
+        Ok(())
+    }
+
+    /// subroutineCall: subroutineName '(' expressionList ')' |
+    /// (className | varName) '.' subroutineName '(' expressionList ')'
+    fn compile_subroutine_call(&mut self) -> Result<(), ParseError> {
+        if self.tokenizer.peek(1) == Some(&Token::Symbol(Symbol::Dot)) {
+            // The receiver is a variable if it is declared, a class otherwise
+            match self.symbol_table.lookup(&self.current_identifier()?) {
+                Some(_) => self.eat_variable()?,
+                None => self.eat_identifier(Category::Class, false)?,
+            }
+            self.eat_symbol(Symbol::Dot)?;
+        }
+        self.eat_identifier(Category::Subroutine, false)?;
+        self.eat_symbol(Symbol::ParenthesisLeft)?;
+        self.compile_expression_list()?;
+        self.eat_symbol(Symbol::ParenthesisRight)?;
+        Ok(())
+    }
+
+    /// The part shared by the if and while statements:
+    /// '(' expression ')' '{' statements '}'
+    fn compile_condition_and_body(&mut self) -> Result<(), ParseError> {
+        self.eat_symbol(Symbol::ParenthesisLeft)?;
+        self.compile_expression()?;
+        self.eat_symbol(Symbol::ParenthesisRight)?;
+        self.eat_symbol(Symbol::CurlLeft)?;
+        self.compile_statements()?;
+        self.eat_symbol(Symbol::CurlRight)?;
+        Ok(())
+    }
+
+    /// type: 'int' | 'char' | 'boolean' | className
+    fn compile_type(&mut self) -> Result<Type, ParseError> {
+        let ty = match self.current_keyword() {
+            Some(Keyword::Int) => Type::Int,
+            Some(Keyword::Char) => Type::Char,
+            Some(Keyword::Boolean) => Type::Boolean,
+            _ => {
+                let name = self
+                    .current_identifier()
+                    .map_err(|_| self.tokenizer.unexpected("a type"))?;
+                self.eat_identifier(Category::Class, false)?;
+                return Ok(Type::Class(name));
+            }
+        };
+        self.write_current_token()?;
+        Ok(ty)
+    }
+
+    /// varName (',' varName)* ';'
+    fn compile_var_names(&mut self, kind: Kind, ty: Type) -> Result<(), ParseError> {
+        self.eat_declaration(kind, ty.clone())?;
+        while self.is_symbol(Symbol::Comma) {
+            self.eat_symbol(Symbol::Comma)?;
+            self.eat_declaration(kind, ty.clone())?;
+        }
+        self.eat_symbol(Symbol::Semicolon)
+    }
+
+    fn current_keyword(&self) -> Option<Keyword> {
+        match self.tokenizer.has_more_tokens() {
+            true => match self.tokenizer.current_token() {
+                Token::Keyword(k) => Some(k.clone()),
+                _ => None,
+            },
+            false => None,
+        }
+    }
+
+    fn is_keyword(&self, keywords: &[Keyword]) -> bool {
+        self.current_keyword()
+            .map(|k| keywords.contains(&k))
+            .unwrap_or_default()
+    }
+
+    fn is_symbol(&self, symbol: Symbol) -> bool {
+        self.tokenizer.has_more_tokens() && *self.tokenizer.current_token() == Token::Symbol(symbol)
+    }
+
+    fn is_op(&self) -> bool {
+        self.tokenizer.has_more_tokens()
+            && matches!(
+                self.tokenizer.current_token(),
+                Token::Symbol(
+                    Symbol::Plus
+                        | Symbol::Minus
+                        | Symbol::Mul
+                        | Symbol::Divide
+                        | Symbol::And
+                        | Symbol::Or
+                        | Symbol::LessThan
+                        | Symbol::MoreThan
+                        | Symbol::Equal
+                )
+            )
+    }
+
+    /// Writes the current keyword if it is one of the given ones and returns it.
+    fn eat_keyword(&mut self, keywords: &[Keyword]) -> Result<Keyword, ParseError> {
+        match self.current_keyword() {
+            Some(keyword) if keywords.contains(&keyword) => {
+                self.write_current_token()?;
+                Ok(keyword)
+            }
+            _ => Err(self
+                .tokenizer
+                .unexpected(&one_of(keywords.iter().map(Keyword::to_str)))),
+        }
+    }
+
+    fn eat_symbol(&mut self, symbol: Symbol) -> Result<(), ParseError> {
+        if !self.is_symbol(symbol.clone()) {
+            return Err(self.tokenizer.unexpected(&format!("`{}`", symbol.to_str())));
+        }
+        self.write_current_token()
+    }
+
+    /// Returns the name of the current identifier token.
+    fn current_identifier(&self) -> Result<String, ParseError> {
+        self.tokenizer.identifier()
+    }
+
+    /// Defines the current identifier as a new variable and writes it.
+    fn eat_declaration(&mut self, kind: Kind, ty: Type) -> Result<(), ParseError> {
+        let name = self.current_identifier()?;
+        self.symbol_table.define(&name, ty, kind);
+        self.eat_identifier(Category::Variable(kind), true)
+    }
+
+    /// Writes the current identifier as a variable usage.
+    fn eat_variable(&mut self) -> Result<(), ParseError> {
+        match self.symbol_table.kind_of(&self.current_identifier()?) {
+            Some(kind) => self.eat_identifier(Category::Variable(kind), false),
+            None => self.write_current_token(),
+        }
+    }
+
+    fn eat_identifier(&mut self, category: Category, declared: bool) -> Result<(), ParseError> {
+        let name = self.current_identifier()?;
+        if !self.extended {
+            return self.write_current_token();
+        }
+
+        let mut attributes = vec![("category", category.to_str().to_string())];
+        if let Category::Variable(_) = category {
+            let index = self.symbol_table.index_of(&name).unwrap_or_default();
+            attributes.push(("index", index.to_string()));
+        }
+        let usage = match declared {
+            true => "declared",
+            false => "used",
+        };
+        attributes.push(("usage", usage.to_string()));
+
+        self.push_current_token(attributes)
+    }
+
+    /// Writes the current token as a terminal element and advances the tokenizer.
+    fn write_current_token(&mut self) -> Result<(), ParseError> {
+        self.push_current_token(Vec::new())
+    }
+
+    /// Adds the current token to the current node and advances the tokenizer.
+    fn push_current_token(
+        &mut self,
+        attributes: Vec<(&'static str, String)>,
+    ) -> Result<(), ParseError> {
+        let token = CstToken {
+            token: self
+                .tokenizer
+                .peek_spanned(0)
+                .cloned()
+                .ok_or_else(|| self.tokenizer.unexpected("a token"))?,
+            text: self.tokenizer.current_text(),
+            leading_trivia: self.tokenizer.leading_trivia().to_vec(),
+            attributes,
+        };
+        self.current_node().children.push(CstElement::Token(token));
+        self.tokenizer.advance();
+        Ok(())
+    }
+
+    fn open(&mut self, kind: &'static str) {
+        self.stack.push(CstNode::new(kind));
+    }
+
+    fn close(&mut self, kind: &'static str) {
+        let node = self.stack.pop().expect("a node is open");
+        debug_assert_eq!(node.kind, kind);
+        self.current_node().children.push(CstElement::Node(node));
+    }
+
+    fn current_node(&mut self) -> &mut CstNode {
+        self.stack.last_mut().expect("a node is open")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn compile_file(path: &str) -> String {
+        let tokenizer = JackTokenizer::new(PathBuf::from(path)).unwrap();
+        CompilationEngine::new(tokenizer).compile().unwrap()
+    }
+
+    #[test]
+    fn test_compile_square() {
+        for name in ["Main", "Square", "SquareGame"] {
+            // Given
+            let path = format!("test_data/Square/{}.jack", name);
+            let expected = std::fs::read_to_string(format!("test_data/Square/{}.xml", name))
+                .expect("failed to read file");
+
+            // When
+            let output = compile_file(&path);
+
+            // Then
+            pretty_assertions::assert_eq!(output, expected);
+        }
+    }
+
+    #[test]
+    fn test_compile_expression_less_square() {
+        for name in ["Main", "Square", "SquareGame"] {
+            // Given
+            let path = format!("test_data/ExpressionLessSquare/{}.jack", name);
+            let expected =
+                std::fs::read_to_string(format!("test_data/ExpressionLessSquare/{}.xml", name))
+                    .expect("failed to read file");
+
+            // When
+            let output = compile_file(&path);
+
+            // Then
+            pretty_assertions::assert_eq!(output, expected);
+        }
+    }
+
+    #[test]
+    fn test_compile_extended() {
+        // Given
+        let input = r"
+        class Point {
+            field int x;
+            static Point origin;
+            method int dist(Point other) {
+                var int dx;
+                let dx = x - other.getX();
+                do Output.printInt(dx);
+                return norm(dx);
+            }
+        }"
+        .to_string();
+
+        // When
+        let output = CompilationEngine::extended(JackTokenizer::from_source(input).unwrap())
+            .compile()
+            .unwrap();
+
+        // Then
+        let identifiers: Vec<_> = output
+            .lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with("<identifier"))
+            .collect();
+        pretty_assertions::assert_eq!(
+            identifiers,
+            vec![
+                r#"<identifier category="class" usage="declared"> Point </identifier>"#,
+                r#"<identifier category="field" index="0" usage="declared"> x </identifier>"#,
+                r#"<identifier category="class" usage="used"> Point </identifier>"#,
+                r#"<identifier category="static" index="0" usage="declared"> origin </identifier>"#,
+                r#"<identifier category="subroutine" usage="declared"> dist </identifier>"#,
+                r#"<identifier category="class" usage="used"> Point </identifier>"#,
+                r#"<identifier category="arg" index="1" usage="declared"> other </identifier>"#,
+                r#"<identifier category="var" index="0" usage="declared"> dx </identifier>"#,
+                r#"<identifier category="var" index="0" usage="used"> dx </identifier>"#,
+                r#"<identifier category="field" index="0" usage="used"> x </identifier>"#,
+                r#"<identifier category="arg" index="1" usage="used"> other </identifier>"#,
+                r#"<identifier category="subroutine" usage="used"> getX </identifier>"#,
+                r#"<identifier category="class" usage="used"> Output </identifier>"#,
+                r#"<identifier category="subroutine" usage="used"> printInt </identifier>"#,
+                r#"<identifier category="var" index="0" usage="used"> dx </identifier>"#,
+                r#"<identifier category="subroutine" usage="used"> norm </identifier>"#,
+                r#"<identifier category="var" index="0" usage="used"> dx </identifier>"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compile_array_test() {
+        // Given
+        let expected =
+            std::fs::read_to_string("test_data/ArrayTest/Main.xml").expect("failed to read file");
+
+        // When
+        let output = compile_file("test_data/ArrayTest/Main.jack");
+
+        // Then
+        pretty_assertions::assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_compile_syntax_errors() {
+        for (input, expected) in [
+            (
+                "class Main { field int; }",
+                "expected an identifier, found `;`",
+            ),
+            (
+                "class Main { function void f() { let x = ; } }",
+                "expected a term, found `;`",
+            ),
+            (
+                "class Main { function void f() { return; }",
+                "expected `}`, found end of input",
+            ),
+            (
+                "class Main { } class",
+                "expected end of input, found `class`",
+            ),
+        ] {
+            // Given
+            let tokenizer = JackTokenizer::from_source(input.to_string()).unwrap();
+
+            // When
+            let error = CompilationEngine::new(tokenizer)
+                .compile_tree()
+                .unwrap_err();
+
+            // Then
+            assert_eq!(error.message(), expected, "{}", input);
+        }
+    }
+}
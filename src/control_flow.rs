@@ -0,0 +1,280 @@
+use crate::{
+    ast::{Class, Expression, KeywordConst, Statement, SubroutineDec, Term},
+    errors::ControlFlowError,
+};
+
+/// The node standing for the start of the body, running before its first
+/// statement.
+const START: usize = 0;
+
+/// The node standing for the end of the body, reached by falling through its
+/// last statement.
+const END: usize = 1;
+
+/// Checks the control flow of the subroutines of a class and returns their
+/// errors in the order of the source. The conditions are unknown, so both
+/// branches of an `if` can run and a `while` can loop or exit, except
+/// `while (true)`, which never exits.
+pub fn check_control_flow(class: &Class) -> Vec<ControlFlowError> {
+    let mut errors = Vec::new();
+    for subroutine in &class.subroutines {
+        let name = format!("{}.{}", class.name.name, subroutine.name.name);
+        errors.extend(check_subroutine(&name, subroutine));
+    }
+    errors
+}
+
+fn check_subroutine(name: &str, subroutine: &SubroutineDec) -> Vec<ControlFlowError> {
+    let statements = &subroutine.body.statements;
+    let mut graph = Graph::default();
+    for exit in graph.add_block(statements, vec![START]) {
+        graph.successors[exit].push(END);
+    }
+    let mut analysis = Analysis {
+        subroutine: name,
+        return_type: subroutine.return_type.as_ref().map(|ty| ty.name()),
+        reachable: graph.reachable(START),
+        next_node: END + 1,
+    };
+
+    let mut errors = Vec::new();
+    if analysis.reachable[END] {
+        errors.push(ControlFlowError::MissingReturn {
+            subroutine: name.to_string(),
+            span: subroutine.name.span.clone(),
+        });
+    }
+    analysis.check_block(statements, &mut errors);
+    errors
+}
+
+/// The control-flow graph of a subroutine body, with `START` and `END`
+/// followed by a node per statement, numbered in the order of the source
+/// (a statement comes before the statements it contains).
+#[derive(Debug)]
+struct Graph {
+    /// The nodes that can run right after each node
+    successors: Vec<Vec<usize>>,
+}
+
+impl Default for Graph {
+    fn default() -> Self {
+        Self {
+            successors: vec![Vec::new(), Vec::new()],
+        }
+    }
+}
+
+impl Graph {
+    /// Adds the statements running one after the other, the first one after
+    /// the `entering` nodes, and returns the nodes falling through the block.
+    fn add_block(&mut self, statements: &[Statement], entering: Vec<usize>) -> Vec<usize> {
+        statements.iter().fold(entering, |entering, statement| {
+            let node = self.successors.len();
+            for previous in entering {
+                self.successors[previous].push(node);
+            }
+            self.add_statement(statement)
+        })
+    }
+
+    /// Adds the statement and the ones it contains, and returns the nodes
+    /// falling through it.
+    fn add_statement(&mut self, statement: &Statement) -> Vec<usize> {
+        let node = self.successors.len();
+        self.successors.push(Vec::new());
+        match statement {
+            Statement::If {
+                statements,
+                else_statements,
+                ..
+            } => {
+                let mut exits = self.add_block(statements, vec![node]);
+                match else_statements {
+                    Some(else_statements) => {
+                        exits.extend(self.add_block(else_statements, vec![node]))
+                    }
+                    None => exits.push(node),
+                }
+                exits
+            }
+            Statement::While {
+                condition,
+                statements,
+                ..
+            } => {
+                for exit in self.add_block(statements, vec![node]) {
+                    self.successors[exit].push(node);
+                }
+                match is_true(condition) {
+                    true => Vec::new(),
+                    false => vec![node],
+                }
+            }
+            Statement::Return(..) => Vec::new(),
+            Statement::Let { .. } | Statement::Do(..) | Statement::Error(_) => vec![node],
+        }
+    }
+
+    /// Returns whether each node can run after the entry.
+    fn reachable(&self, entry: usize) -> Vec<bool> {
+        let mut reachable = vec![false; self.successors.len()];
+        let mut stack = vec![entry];
+        while let Some(node) = stack.pop() {
+            if !reachable[node] {
+                reachable[node] = true;
+                stack.extend(&self.successors[node]);
+            }
+        }
+        reachable
+    }
+}
+
+/// Whether the condition is the constant `true`.
+fn is_true(condition: &Expression) -> bool {
+    condition.ops.is_empty() && matches!(condition.term, Term::KeywordConst(KeywordConst::True, _))
+}
+
+/// The reachable statements of a subroutine, along with what its returns
+/// must look like.
+struct Analysis<'a> {
+    /// The full name of the subroutine (e.g. `Square.new`)
+    subroutine: &'a str,
+    /// The name of the return type, `None` for `void` subroutines
+    return_type: Option<&'a str>,
+    /// Whether each node of the graph can run
+    reachable: Vec<bool>,
+    /// The node of the next statement, the statements being checked in the
+    /// order of their nodes
+    next_node: usize,
+}
+
+impl Analysis<'_> {
+    /// Checks the statements of a block, reporting the first one that can't
+    /// run after one that can, since the following ones can't either.
+    fn check_block(&mut self, statements: &[Statement], errors: &mut Vec<ControlFlowError>) {
+        let mut previous: Option<(&Statement, bool)> = None;
+        for statement in statements {
+            let reachable = self.reachable[self.next_node];
+            self.next_node += 1;
+            if let (false, Some((previous, true))) = (reachable, previous) {
+                errors.push(ControlFlowError::Unreachable {
+                    span: statement.span().clone(),
+                    after: previous.span().clone(),
+                });
+            }
+            self.check_statement(statement, reachable, errors);
+            previous = Some((statement, reachable));
+        }
+    }
+
+    /// Checks the statement and the ones it contains, which run only if it
+    /// is `reachable`.
+    fn check_statement(
+        &mut self,
+        statement: &Statement,
+        reachable: bool,
+        errors: &mut Vec<ControlFlowError>,
+    ) {
+        match statement {
+            Statement::If {
+                statements,
+                else_statements,
+                ..
+            } => {
+                self.check_block(statements, errors);
+                if let Some(else_statements) = else_statements {
+                    self.check_block(else_statements, errors);
+                }
+            }
+            Statement::While { statements, .. } => self.check_block(statements, errors),
+            Statement::Return(value, span) if reachable => match (value, self.return_type) {
+                (None, Some(return_type)) => errors.push(ControlFlowError::MissingValue {
+                    subroutine: self.subroutine.to_string(),
+                    return_type: return_type.to_string(),
+                    span: span.clone(),
+                }),
+                (Some(value), None) => errors.push(ControlFlowError::UnexpectedValue {
+                    subroutine: self.subroutine.to_string(),
+                    span: value.span.clone(),
+                }),
+                _ => {}
+            },
+            Statement::Return(..)
+            | Statement::Let { .. }
+            | Statement::Do(..)
+            | Statement::Error(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::check_sources;
+
+    #[test]
+    fn test_check_returns() {
+        // Given
+        let source = "class Main {
+  function int f(boolean b) {
+    if (b) { return 1; } else { return; }
+  }
+  function void g(boolean b) {
+    while (b) { return b; }
+  }
+  function int h(boolean b) {
+    if (b) { return 1; } else { return 2; }
+  }
+  function int loop() {
+    while (true) { }
+  }
+}";
+
+        // When
+        let errors = check_sources(&[source], |class, _| check_control_flow(class));
+
+        // Then
+        pretty_assertions::assert_eq!(
+            errors,
+            vec![
+                (
+                    3,
+                    33,
+                    String::from("`Main.f` must return a value of type `int`")
+                ),
+                (
+                    5,
+                    17,
+                    String::from("`Main.g` can reach its end without returning")
+                ),
+                (6, 24, String::from("`Main.g` is void but returns a value")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_unreachable() {
+        // Given
+        let source = "class Main {
+  function void f(boolean b) {
+    if (b) { return; if (b) { return 1; } do f(b); }
+    while (true) { do f(b); }
+    if (b) { do f(b); }
+    return;
+  }
+}";
+
+        // When
+        let errors = check_sources(&[source], |class, _| check_control_flow(class));
+
+        // Then
+        pretty_assertions::assert_eq!(
+            errors,
+            vec![
+                (3, 22, String::from("unreachable statement")),
+                (5, 5, String::from("unreachable statement")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{
+    dot,
+    json::JsonTree,
+    tokens::{SpannedToken, Trivia},
+};
+
+/// Number of spaces added for each level of nesting in the XML output.
+const INDENT_WIDTH: usize = 2;
+
+/// A terminal of the concrete syntax tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstToken {
+    pub token: SpannedToken<'static>,
+    /// The token as written in the source
+    pub text: String,
+    /// The whitespace and comments preceding the token
+    pub leading_trivia: Vec<Trivia<'static>>,
+    /// The attributes of the XML element (e.g. `category="var"`)
+    pub attributes: Vec<(&'static str, String)>,
+}
+
+impl CstToken {
+    /// Returns the token as a single XML element (e.g. `<symbol> { </symbol>`).
+    pub fn to_xml_element(&self) -> String {
+        let token = &self.token.token;
+        token.start_xml_with_attributes(&self.attributes)
+            + " "
+            + &token.to_xml()
+            + " "
+            + &token.end_xml()
+    }
+}
+
+/// A child of a nonterminal: either another nonterminal or a token.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CstElement {
+    Node(CstNode),
+    Token(CstToken),
+}
+
+/// A nonterminal of the concrete syntax tree, named after its grammar rule
+/// (e.g. `classVarDec`, `statements`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstNode {
+    pub kind: &'static str,
+    pub children: Vec<CstElement>,
+}
+
+impl CstNode {
+    pub fn new(kind: &'static str) -> Self {
+        Self {
+            kind,
+            children: Vec::new(),
+        }
+    }
+
+    /// Returns the tokens of the node in source order.
+    pub fn tokens(&self) -> Vec<&CstToken> {
+        let mut tokens = Vec::new();
+        self.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a CstToken>) {
+        for child in &self.children {
+            match child {
+                CstElement::Node(node) => node.collect_tokens(tokens),
+                CstElement::Token(token) => tokens.push(token),
+            }
+        }
+    }
+
+    fn write_xml(&self, depth: usize, output: &mut String) {
+        let indent = " ".repeat(depth * INDENT_WIDTH);
+        *output += &format!("{}<{}>\n", indent, self.kind);
+        for child in &self.children {
+            match child {
+                CstElement::Node(node) => node.write_xml(depth + 1, output),
+                CstElement::Token(token) => {
+                    *output += &" ".repeat((depth + 1) * INDENT_WIDTH);
+                    *output += &token.to_xml_element();
+                    *output += "\n";
+                }
+            }
+        }
+        *output += &format!("{}</{}>\n", indent, self.kind);
+    }
+}
+
+/// A lossless syntax tree of a class: printing its tokens along with
+/// their trivia reproduces the source exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cst {
+    pub root: CstNode,
+    /// The whitespace and comments following the last token
+    pub trailing_trivia: Vec<Trivia<'static>>,
+}
+
+impl Cst {
+    /// Returns the parse tree XML, which leaves out the trivia.
+    pub fn to_xml(&self) -> String {
+        let mut output = String::new();
+        self.root.write_xml(0, &mut output);
+        output
+    }
+
+    /// Returns the parse tree as JSON, following the schema of the `json` module.
+    pub fn to_json(&self) -> String {
+        JsonTree::new(self).to_json()
+    }
+
+    /// Returns the parse tree as a Graphviz DOT graph, see `dot::to_dot`.
+    pub fn to_dot(&self, collapse: bool) -> String {
+        dot::to_dot(self, collapse)
+    }
+
+    /// Returns the source code the tree was built from.
+    pub fn to_source(&self) -> String {
+        let mut output = String::new();
+        for token in self.root.tokens() {
+            for trivia in &token.leading_trivia {
+                output += &trivia.text;
+            }
+            output += &token.text;
+        }
+        for trivia in &self.trailing_trivia {
+            output += &trivia.text;
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compilation_engine::CompilationEngine, tokenizer::JackTokenizer};
+
+    fn cst(source: &str) -> Cst {
+        let tokenizer = JackTokenizer::from_source_lossless(source.to_string()).unwrap();
+        CompilationEngine::new(tokenizer).compile_tree().unwrap()
+    }
+
+    #[test]
+    fn test_to_source() {
+        // Given
+        let source = "/** Doc. */\r\nclass  Main {\n\tfield int x; // the x\n\n  /* f */ function void f() {\n    let x = 007;\n    return;\n  }\n}\n\n// end\n";
+
+        // When
+        let cst = cst(source);
+
+        // Then
+        assert_eq!(cst.to_source(), source);
+        assert_eq!(cst.trailing_trivia.len(), 3);
+    }
+
+    #[test]
+    fn test_leading_trivia() {
+        // Given
+        let source = "class Main { /** The x. */\n field int x; }";
+
+        // When
+        let cst = cst(source);
+
+        // Then
+        let tokens = cst.root.tokens();
+        assert_eq!(tokens[3].text, "field");
+        let trivia: Vec<_> = tokens[3]
+            .leading_trivia
+            .iter()
+            .map(|t| t.text.as_ref())
+            .collect();
+        assert_eq!(trivia, vec![" ", "/** The x. */", "\n "]);
+        assert!(tokens[0].leading_trivia.is_empty());
+    }
+
+    #[test]
+    fn test_to_xml() {
+        // Given
+        let source = "class Main { }";
+
+        // When
+        let xml = cst(source).to_xml();
+
+        // Then
+        assert_eq!(
+            xml,
+            "<class>\n  <keyword> class </keyword>\n  <identifier> Main </identifier>\n  <symbol> { </symbol>\n  <symbol> } </symbol>\n</class>\n"
+        );
+    }
+}
@@ -0,0 +1,602 @@
+use std::path::Path;
+
+use crate::{
+    errors::{CodeGenError, ControlFlowError, LexError, ParseError, SemanticError, TypeError},
+    tokens::Span,
+};
+
+/// Number of spaces a tab is shown as in the source snippets.
+const TAB_WIDTH: usize = 4;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn to_str(&self) -> &str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+        }
+    }
+
+    fn color(&self) -> &'static str {
+        match self {
+            Self::Error => RED,
+            Self::Warning => YELLOW,
+        }
+    }
+}
+
+/// A location in the source along with what happens there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// An error or warning about a file, pointing at the source it is about.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The code identifying the kind of diagnostic (e.g. `E0100`)
+    pub code: Option<&'static str>,
+    pub message: String,
+    /// The location the diagnostic is about, if any
+    pub primary: Option<Label>,
+    /// The related locations (e.g. where a class is declared)
+    pub secondary: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message.into())
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message.into())
+    }
+
+    fn new(severity: Severity, message: String) -> Self {
+        Self {
+            severity,
+            code: None,
+            message,
+            primary: None,
+            secondary: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_primary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.primary = Some(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.secondary.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+}
+
+impl From<&LexError> for Diagnostic {
+    fn from(error: &LexError) -> Self {
+        let diagnostic = Self::error(error.message()).with_code(error.code());
+        match error.span() {
+            Some(span) => diagnostic.with_primary(span.clone(), ""),
+            None => diagnostic,
+        }
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(error: &ParseError) -> Self {
+        let diagnostic = Self::error(error.message()).with_code(error.code());
+        match error {
+            ParseError::UnexpectedToken { expected, span, .. }
+            | ParseError::UnexpectedEndOfInput { expected, span } => {
+                diagnostic.with_primary(span.clone(), format!("expected {}", expected))
+            }
+            ParseError::UnclosedDelimiter {
+                expected,
+                span,
+                delimiter,
+                opening,
+                ..
+            } => diagnostic
+                .with_primary(span.clone(), format!("expected {}", expected))
+                .with_secondary(
+                    (**opening).clone(),
+                    format!("unclosed `{}`", delimiter.to_str()),
+                ),
+            ParseError::Lex(e) => Self::from(e),
+        }
+    }
+}
+
+impl From<&CodeGenError> for Diagnostic {
+    fn from(error: &CodeGenError) -> Self {
+        let diagnostic = Self::error(error.to_string()).with_code(error.code());
+        match error {
+            CodeGenError::UndeclaredVariable { span, .. } => {
+                diagnostic.with_primary(span.clone(), "not declared in this scope")
+            }
+        }
+    }
+}
+
+impl From<&SemanticError> for Diagnostic {
+    fn from(error: &SemanticError) -> Self {
+        let diagnostic = Self::error(error.message()).with_code(error.code());
+        let span = error.span().clone();
+        match error {
+            SemanticError::UndeclaredVariable { .. } => {
+                diagnostic.with_primary(span, "not declared in this scope")
+            }
+            SemanticError::UnknownClass { .. } => {
+                diagnostic.with_primary(span, "not a class of the program nor of the OS")
+            }
+            SemanticError::UnknownSubroutine {
+                class, class_span, ..
+            } => declared(
+                diagnostic.with_primary(span, "not found in the class"),
+                class_span,
+                &format!("class `{}`", class),
+            ),
+            SemanticError::ArgumentCount {
+                expected,
+                declaration,
+                ..
+            } => declared(
+                diagnostic.with_primary(span, format!("expected {}", expected)),
+                declaration,
+                "subroutine",
+            ),
+            SemanticError::ThisInFunction { .. } => diagnostic
+                .with_primary(span, "no object in a function")
+                .with_note("only constructors and methods operate on an object"),
+            SemanticError::MethodCalledAsFunction { declaration, .. } => declared(
+                diagnostic.with_primary(span, "called without an object"),
+                declaration,
+                "method",
+            )
+            .with_note("call the method on an object, e.g. `object.method()`"),
+            SemanticError::DuplicateDeclaration { first, .. } => diagnostic
+                .with_primary(span, "declared again here")
+                .with_secondary(first.clone(), "first declared here"),
+            SemanticError::FieldInFunction { .. } => diagnostic
+                .with_primary(span, "no object in a function")
+                .with_note("only constructors and methods operate on an object"),
+        }
+    }
+}
+
+/// Points at the declaration of `what`, or notes that the OS declares it.
+fn declared(diagnostic: Diagnostic, declaration: &Option<Span>, what: &str) -> Diagnostic {
+    match declaration {
+        Some(span) => diagnostic.with_secondary(span.clone(), format!("{} declared here", what)),
+        None => diagnostic.with_note(format!("{} declared by the Jack OS", what)),
+    }
+}
+
+/// Unreachable statements are warnings, the other paths the VM can't run
+/// are errors.
+impl From<&ControlFlowError> for Diagnostic {
+    fn from(error: &ControlFlowError) -> Self {
+        let span = error.span().clone();
+        match error {
+            ControlFlowError::MissingReturn { .. } => Self::error(error.message())
+                .with_primary(span, "a path through this subroutine doesn't return")
+                .with_note("the VM requires every subroutine to end with `return`"),
+            ControlFlowError::MissingValue { return_type, .. } => Self::error(error.message())
+                .with_primary(span, format!("expected a value of type `{}`", return_type)),
+            ControlFlowError::UnexpectedValue { .. } => Self::error(error.message())
+                .with_primary(span, "not returned by a void subroutine")
+                .with_note("void subroutines end with `return;`"),
+            ControlFlowError::Unreachable { after, .. } => Self::warning(error.message())
+                .with_primary(span, "unreachable statement")
+                .with_secondary(after.clone(), "any code following this is unreachable"),
+        }
+        .with_code(error.code())
+    }
+}
+
+/// Type errors are warnings unless the checks are strict, see `with_severity`.
+impl From<&TypeError> for Diagnostic {
+    fn from(error: &TypeError) -> Self {
+        let diagnostic = Self::warning(error.message()).with_code(error.code());
+        let span = error.span().clone();
+        match error {
+            TypeError::Mismatch { expected, .. } => {
+                diagnostic.with_primary(span, format!("expected `{}`", expected))
+            }
+            TypeError::InvalidOperand { found, .. } => {
+                diagnostic.with_primary(span, format!("found `{}`", found))
+            }
+            TypeError::NotAnArray { .. } => {
+                diagnostic.with_primary(span, "only an `Array` can be indexed")
+            }
+            TypeError::VoidValue { .. } => diagnostic.with_primary(span, "used as a value"),
+        }
+    }
+}
+
+/// Renders diagnostics in the layout of rustc: a header with the code and
+/// message, the location, then the source lines of the labels with the
+/// labelled ranges underlined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Renderer {
+    /// Whether to color the output with ANSI escape codes
+    color: bool,
+}
+
+impl Renderer {
+    pub fn new(color: bool) -> Self {
+        Self { color }
+    }
+
+    /// Renders the diagnostic about `file`, whose content is `source`.
+    /// Secondary labels in other files are rendered as notes.
+    pub fn render(&self, diagnostic: &Diagnostic, file: &Path, source: &str) -> String {
+        let severity = diagnostic.severity;
+        let in_file = |label: &&Label| {
+            label
+                .span
+                .file
+                .as_deref()
+                .map(|f| f == file)
+                .unwrap_or(true)
+        };
+        let mut labels: Vec<(&Label, bool)> = diagnostic
+            .primary
+            .iter()
+            .map(|label| (label, true))
+            .chain(
+                diagnostic
+                    .secondary
+                    .iter()
+                    .filter(in_file)
+                    .map(|label| (label, false)),
+            )
+            .collect();
+        // Sorting is stable, so the primary label comes first on its line
+        labels.sort_by_key(|(label, _)| label.span.line);
+        let notes: Vec<String> = diagnostic
+            .secondary
+            .iter()
+            .filter(|label| !in_file(label))
+            .map(|label| format!("{}: {}", label.span, label.message))
+            .chain(diagnostic.notes.iter().cloned())
+            .collect();
+
+        let width = labels
+            .iter()
+            .map(|(label, _)| label.span.line.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let gutter = " ".repeat(width);
+        let bar = self.paint(BLUE, "|");
+
+        let mut output = match diagnostic.code {
+            Some(code) => self.paint(
+                severity.color(),
+                &format!("{}[{}]", severity.to_str(), code),
+            ),
+            None => self.paint(severity.color(), severity.to_str()),
+        };
+        output += &self.paint(BOLD, &format!(": {}", diagnostic.message));
+        output += "\n";
+        let location = match &diagnostic.primary {
+            Some(label) => format!(
+                "{}:{}:{}",
+                file.display(),
+                label.span.line,
+                label.span.column
+            ),
+            None => file.display().to_string(),
+        };
+        output += &format!("{}{} {}\n", gutter, self.paint(BLUE, "-->"), location);
+
+        if !labels.is_empty() {
+            output += &format!("{} {}\n", gutter, bar);
+        }
+        let mut previous_line = None;
+        for (i, (label, primary)) in labels.iter().enumerate() {
+            let line = label.span.line;
+            if previous_line != Some(line) {
+                if previous_line.is_some_and(|previous| line > previous + 1) {
+                    output += &self.paint(BLUE, "...");
+                    output += "\n";
+                }
+                let text = source.lines().nth(line.saturating_sub(1)).unwrap_or("");
+                let snippet = format!(
+                    "{} {} {}",
+                    self.paint(BLUE, &format!("{:>width$}", line)),
+                    bar,
+                    expand_tabs(text)
+                );
+                output += snippet.trim_end();
+                output += "\n";
+            }
+            previous_line = Some(line);
+
+            let text = source.lines().nth(line.saturating_sub(1)).unwrap_or("");
+            let (offset, length) = underline(label, text, source);
+            let (mark, color) = match primary {
+                true => ("^", severity.color()),
+                false => ("-", BLUE),
+            };
+            let marks = mark.repeat(length) + " " + &label.message;
+            output += &format!(
+                "{} {} {}{}\n",
+                gutter,
+                bar,
+                " ".repeat(offset),
+                self.paint(color, marks.trim_end())
+            );
+            if i == labels.len() - 1 && !notes.is_empty() {
+                output += &format!("{} {}\n", gutter, bar);
+            }
+        }
+
+        for note in notes {
+            output += &format!("{} {} note: {}\n", gutter, self.paint(BLUE, "="), note);
+        }
+        output
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        match self.color {
+            true => format!("{}{}{}", style, text, RESET),
+            false => text.to_string(),
+        }
+    }
+}
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+/// Returns the offset of the label in the displayed line and the length of
+/// its underline, which stops at the end of the line and is at least one
+/// character long (e.g. at the end of the input).
+fn underline(label: &Label, line: &str, source: &str) -> (usize, usize) {
+    let prefix: String = line
+        .chars()
+        .take(label.span.column.saturating_sub(1))
+        .collect();
+    let text = source
+        .get(label.span.start..label.span.end)
+        .unwrap_or("")
+        .lines()
+        .next()
+        .unwrap_or("");
+    let length = expand_tabs(text).chars().count().max(1);
+    (expand_tabs(&prefix).chars().count(), length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_class;
+
+    #[test]
+    fn test_render_parse_error() {
+        // Given
+        let source = "class Main {\n\tfunction void main() {\n\t\tdo f(;\n\t}\n}\n";
+        let error = parse_class(source).unwrap_err();
+
+        // When
+        let rendered =
+            Renderer::new(false).render(&Diagnostic::from(&error), Path::new("Main.jack"), source);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            rendered,
+            "error[E0100]: expected a term, found `;`
+ --> Main.jack:3:8
+  |
+3 |         do f(;
+  |              ^ expected a term
+"
+        );
+    }
+
+    #[test]
+    fn test_render_secondary_label() {
+        // Given
+        let source = "class Main {\n  function void main() {\n    return;\n\n\n}\n";
+        let error = parse_class(source).unwrap_err();
+
+        // When
+        let rendered =
+            Renderer::new(false).render(&Diagnostic::from(&error), Path::new("Main.jack"), source);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            rendered,
+            "error[E0102]: expected `}`, found end of input
+ --> Main.jack:7:1
+  |
+1 | class Main {
+  |            - unclosed `{`
+...
+7 |
+  | ^ expected `}`
+"
+        );
+    }
+
+    #[test]
+    fn test_render_notes() {
+        // Given
+        let other = Span {
+            file: Some(Path::new("Other.jack").into()),
+            start: 6,
+            end: 11,
+            line: 1,
+            column: 7,
+        };
+        let diagnostic = Diagnostic::warning("unused class")
+            .with_secondary(other, "class declared here")
+            .with_note("classes are compiled separately");
+
+        // When
+        let rendered = Renderer::new(false).render(&diagnostic, Path::new("Main.jack"), "");
+
+        // Then
+        pretty_assertions::assert_eq!(
+            rendered,
+            "warning: unused class
+ --> Main.jack
+  = note: Other.jack:1:7: class declared here
+  = note: classes are compiled separately
+"
+        );
+    }
+
+    #[test]
+    fn test_render_color() {
+        // Given
+        let source = "class 1";
+        let error = parse_class(source).unwrap_err();
+
+        // When
+        let rendered =
+            Renderer::new(true).render(&Diagnostic::from(&error), Path::new("A.jack"), source);
+
+        // Then
+        assert!(rendered.starts_with("\x1b[1;31merror[E0100]\x1b[0m\x1b[1m: expected"));
+        assert!(rendered.contains("\x1b[1;31m^ expected an identifier\x1b[0m"));
+    }
+
+    #[test]
+    fn test_from_code_gen_error() {
+        // Given
+        let source = "class Main { function void main() { let x = 1; return; } }";
+        let class = parse_class(source).unwrap();
+        let errors = crate::code_generator::compile_class(&class).unwrap_err();
+
+        // When
+        let diagnostic = Diagnostic::from(&errors[0]);
+
+        // Then
+        assert_eq!(diagnostic.code, Some("E0200"));
+        pretty_assertions::assert_eq!(
+            Renderer::new(false).render(&diagnostic, Path::new("Main.jack"), source),
+            "error[E0200]: undeclared variable `x` in Main.main
+ --> Main.jack:1:41
+  |
+1 | class Main { function void main() { let x = 1; return; } }
+  |                                         ^ not declared in this scope
+"
+        );
+    }
+
+    #[test]
+    fn test_render_semantic_error() {
+        // Given
+        let source = "class Main {\n  function void f(int a) { return; }\n  function void g() { do f(); return; }\n}\n";
+        let class = parse_class(source).unwrap();
+        let program = crate::checker::Program::new([&class]);
+        let errors = crate::checker::check_class(&class, &program);
+
+        // When
+        let rendered = Renderer::new(false).render(
+            &Diagnostic::from(&errors[0]),
+            Path::new("Main.jack"),
+            source,
+        );
+
+        // Then
+        pretty_assertions::assert_eq!(
+            rendered,
+            "error[E0303]: `Main.f` takes 1 argument but 0 were given
+ --> Main.jack:3:26
+  |
+2 |   function void f(int a) { return; }
+  |                 - subroutine declared here
+3 |   function void g() { do f(); return; }
+  |                          ^ expected 1
+"
+        );
+    }
+
+    #[test]
+    fn test_from_type_error() {
+        // Given
+        let source = "class Main { function int f() { return true; } }";
+        let class = parse_class(source).unwrap();
+        let program = crate::checker::Program::new([&class]);
+        let errors = crate::type_checker::check_types(&class, &program);
+
+        // When
+        let warning = Diagnostic::from(&errors[0]);
+        let error = warning.clone().with_severity(Severity::Error);
+
+        // Then
+        assert_eq!(warning.severity, Severity::Warning);
+        let rendered = Renderer::new(false).render(&error, Path::new("Main.jack"), source);
+        assert!(rendered.starts_with(
+            "error[E0400]: mismatched types: expected `int`, found `boolean`\n --> Main.jack:1:40\n"
+        ));
+    }
+
+    #[test]
+    fn test_from_control_flow_error() {
+        // Given
+        let source = "class Main { function void f() { return; do f(); } }";
+        let class = parse_class(source).unwrap();
+        let errors = crate::control_flow::check_control_flow(&class);
+
+        // When
+        let diagnostic = Diagnostic::from(&errors[0]);
+
+        // Then
+        assert_eq!(diagnostic.severity, Severity::Warning);
+        assert_eq!(diagnostic.code, Some("E0503"));
+        pretty_assertions::assert_eq!(
+            diagnostic.secondary,
+            vec![Label {
+                span: Span {
+                    file: None,
+                    start: 33,
+                    end: 40,
+                    line: 1,
+                    column: 34,
+                },
+                message: String::from("any code following this is unreachable"),
+            }]
+        );
+    }
+}
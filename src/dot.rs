@@ -0,0 +1,431 @@
+use crate::{
+    ast::{
+        Class, ClassVarKind, Expression, Identifier, KeywordConst, Statement, SubroutineCall,
+        SubroutineDec, SubroutineKind, Term, Type,
+    },
+    cst::{Cst, CstElement, CstNode, CstToken},
+};
+
+/// Writes the parse tree as a Graphviz DOT graph. Nonterminals are
+/// ellipses labelled with their grammar rule and terminals are boxes
+/// labelled with their XML text.
+///
+/// With `collapse`, a chain of nonterminals having a single child is drawn
+/// as one vertex listing their rules (e.g. `expression / term`), which
+/// keeps the trees of deeply nested expressions readable.
+pub fn to_dot(cst: &Cst, collapse: bool) -> String {
+    let mut writer = DotWriter::new("parse_tree", collapse);
+    writer.node(&cst.root, Vec::new());
+    writer.finish()
+}
+
+/// Writes the abstract syntax tree of the class as a Graphviz DOT graph.
+/// Declarations, statements and operations are ellipses, and constants and
+/// variables are boxes. The terms of an expression are grouped from left to
+/// right, in the order Jack evaluates them, and the edges to the parts of a
+/// statement (e.g. the condition and the branches of an `if`) are labelled.
+pub fn ast_to_dot(class: &Class) -> String {
+    let mut writer = DotWriter::new("ast", false);
+    writer.class(class);
+    writer.finish()
+}
+
+struct DotWriter {
+    collapse: bool,
+    output: String,
+    next_id: usize,
+}
+
+impl DotWriter {
+    fn new(name: &str, collapse: bool) -> Self {
+        Self {
+            collapse,
+            output: format!("digraph {} {{\n    ordering=out;\n", name),
+            next_id: 0,
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.output += "}\n";
+        self.output
+    }
+
+    /// Writes the vertex of the node, prefixed with the rules of the
+    /// collapsed nonterminals above it, and returns its id.
+    fn node(&mut self, node: &CstNode, mut rules: Vec<&'static str>) -> usize {
+        rules.push(node.kind);
+        match node.children.as_slice() {
+            [child] if self.collapse => return self.element(child, rules),
+            _ => {}
+        }
+
+        let id = self.vertex(&rules.join(" / "), None);
+        for child in &node.children {
+            let child_id = self.element(child, Vec::new());
+            self.edge(id, child_id, None);
+        }
+        id
+    }
+
+    fn element(&mut self, element: &CstElement, rules: Vec<&'static str>) -> usize {
+        match element {
+            CstElement::Node(node) => self.node(node, rules),
+            CstElement::Token(token) => self.token(token, rules),
+        }
+    }
+
+    fn token(&mut self, token: &CstToken, rules: Vec<&'static str>) -> usize {
+        let text = token.token.token.to_xml();
+        let label: Vec<_> = rules.into_iter().chain([text.as_str()]).collect();
+        self.vertex(&label.join(" / "), Some("box"))
+    }
+
+    fn class(&mut self, class: &Class) {
+        let id = self.vertex(&format!("class {}", class.name.name), None);
+        for var_dec in &class.var_decs {
+            let kind = match var_dec.kind {
+                ClassVarKind::Static => "static",
+                ClassVarKind::Field => "field",
+            };
+            let label = declaration(kind, &var_dec.ty, &var_dec.names);
+            let var_id = self.vertex(&label, Some("box"));
+            self.edge(id, var_id, None);
+        }
+        for subroutine in &class.subroutines {
+            let subroutine_id = self.subroutine(subroutine);
+            self.edge(id, subroutine_id, None);
+        }
+    }
+
+    fn subroutine(&mut self, subroutine: &SubroutineDec) -> usize {
+        let kind = match subroutine.kind {
+            SubroutineKind::Constructor => "constructor",
+            SubroutineKind::Function => "function",
+            SubroutineKind::Method => "method",
+        };
+        let parameters: Vec<_> = subroutine
+            .parameters
+            .iter()
+            .map(|parameter| format!("{} {}", parameter.ty.name(), parameter.name.name))
+            .collect();
+        let label = format!(
+            "{} {} {}({})",
+            kind,
+            subroutine.return_type.as_ref().map_or("void", Type::name),
+            subroutine.name.name,
+            parameters.join(", ")
+        );
+        let id = self.vertex(&label, None);
+        for var_dec in &subroutine.body.var_decs {
+            let var_id = self.vertex(
+                &declaration("var", &var_dec.ty, &var_dec.names),
+                Some("box"),
+            );
+            self.edge(id, var_id, None);
+        }
+        self.statements(id, &subroutine.body.statements, None);
+        id
+    }
+
+    /// Writes the statements as children of the vertex, with the label on
+    /// the edges.
+    fn statements(&mut self, parent: usize, statements: &[Statement], label: Option<&str>) {
+        for statement in statements {
+            let id = self.statement(statement);
+            self.edge(parent, id, label);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) -> usize {
+        match statement {
+            Statement::Let {
+                name, index, value, ..
+            } => {
+                let id = self.vertex(&format!("let {}", name.name), None);
+                if let Some(index) = index {
+                    let index_id = self.expression(index);
+                    self.edge(id, index_id, Some("index"));
+                }
+                let value_id = self.expression(value);
+                self.edge(id, value_id, None);
+                id
+            }
+            Statement::If {
+                condition,
+                statements,
+                else_statements,
+                ..
+            } => {
+                let id = self.vertex("if", None);
+                let condition_id = self.expression(condition);
+                self.edge(id, condition_id, Some("condition"));
+                self.statements(id, statements, Some("then"));
+                if let Some(else_statements) = else_statements {
+                    self.statements(id, else_statements, Some("else"));
+                }
+                id
+            }
+            Statement::While {
+                condition,
+                statements,
+                ..
+            } => {
+                let id = self.vertex("while", None);
+                let condition_id = self.expression(condition);
+                self.edge(id, condition_id, Some("condition"));
+                self.statements(id, statements, Some("body"));
+                id
+            }
+            Statement::Do(call, _) => {
+                let id = self.vertex("do", None);
+                let call_id = self.call(call);
+                self.edge(id, call_id, None);
+                id
+            }
+            Statement::Return(value, _) => {
+                let id = self.vertex("return", None);
+                if let Some(value) = value {
+                    let value_id = self.expression(value);
+                    self.edge(id, value_id, None);
+                }
+                id
+            }
+            Statement::Error(_) => self.vertex("error", Some("box")),
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) -> usize {
+        let mut id = self.term(&expression.term);
+        for (op, term) in &expression.ops {
+            let op_id = self.vertex(op.to_symbol().to_str(), None);
+            let term_id = self.term(term);
+            self.edge(op_id, id, None);
+            self.edge(op_id, term_id, None);
+            id = op_id;
+        }
+        id
+    }
+
+    fn term(&mut self, term: &Term) -> usize {
+        match term {
+            Term::IntConst(value, _) => self.vertex(&value.to_string(), Some("box")),
+            Term::StringConst(value, _) => self.vertex(&format!("\"{}\"", value), Some("box")),
+            Term::KeywordConst(keyword, _) => self.vertex(keyword_const(keyword), Some("box")),
+            Term::VarName(name) => self.vertex(&name.name, Some("box")),
+            Term::ArrayAccess(name, index, _) => {
+                let id = self.vertex(&format!("{}[]", name.name), None);
+                let index_id = self.expression(index);
+                self.edge(id, index_id, Some("index"));
+                id
+            }
+            Term::Call(call, _) => self.call(call),
+            Term::Parenthesized(expression, _) => self.expression(expression),
+            Term::Unary(op, term, _) => {
+                let id = self.vertex(op.to_symbol().to_str(), None);
+                let term_id = self.term(term);
+                self.edge(id, term_id, None);
+                id
+            }
+        }
+    }
+
+    fn call(&mut self, call: &SubroutineCall) -> usize {
+        let label = match &call.receiver {
+            Some(receiver) => format!("{}.{}()", receiver.name, call.name.name),
+            None => format!("{}()", call.name.name),
+        };
+        let id = self.vertex(&label, None);
+        for argument in &call.arguments {
+            let argument_id = self.expression(argument);
+            self.edge(id, argument_id, None);
+        }
+        id
+    }
+
+    fn vertex(&mut self, label: &str, shape: Option<&str>) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        let shape = shape
+            .map(|shape| format!(", shape={}", shape))
+            .unwrap_or_default();
+        self.output += &format!("    n{} [label=\"{}\"{}];\n", id, escape(label), shape);
+        id
+    }
+
+    fn edge(&mut self, from: usize, to: usize, label: Option<&str>) {
+        let label = label
+            .map(|label| format!(" [label=\"{}\"]", escape(label)))
+            .unwrap_or_default();
+        self.output += &format!("    n{} -> n{}{};\n", from, to, label);
+    }
+}
+
+/// Returns the label of a variable declaration, e.g. `var int x, y`.
+fn declaration(kind: &str, ty: &Type, names: &[Identifier]) -> String {
+    let names: Vec<_> = names.iter().map(|name| name.name.as_str()).collect();
+    format!("{} {} {}", kind, ty.name(), names.join(", "))
+}
+
+fn keyword_const(keyword: &KeywordConst) -> &'static str {
+    match keyword {
+        KeywordConst::True => "true",
+        KeywordConst::False => "false",
+        KeywordConst::Null => "null",
+        KeywordConst::This => "this",
+    }
+}
+
+/// Escapes the characters that end or escape a DOT string.
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compilation_engine::CompilationEngine, tokenizer::JackTokenizer};
+
+    fn cst(source: &str) -> Cst {
+        let tokenizer = JackTokenizer::from_source(source.to_string()).unwrap();
+        CompilationEngine::new(tokenizer).compile_tree().unwrap()
+    }
+
+    #[test]
+    fn test_to_dot() {
+        // Given
+        let cst = cst("class A { field int x; }");
+
+        // When
+        let dot = to_dot(&cst, false);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            dot,
+            r#"digraph parse_tree {
+    ordering=out;
+    n0 [label="class"];
+    n1 [label="class", shape=box];
+    n0 -> n1;
+    n2 [label="A", shape=box];
+    n0 -> n2;
+    n3 [label="{", shape=box];
+    n0 -> n3;
+    n4 [label="classVarDec"];
+    n5 [label="field", shape=box];
+    n4 -> n5;
+    n6 [label="int", shape=box];
+    n4 -> n6;
+    n7 [label="x", shape=box];
+    n4 -> n7;
+    n8 [label=";", shape=box];
+    n4 -> n8;
+    n0 -> n4;
+    n9 [label="}", shape=box];
+    n0 -> n9;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_to_dot_collapse() {
+        // Given
+        let cst = cst(r#"class A { function void f() { do g(x); return y < "a\"; } }"#);
+
+        // When
+        let dot = to_dot(&cst, true);
+
+        // Then
+        let labels: Vec<_> = dot
+            .lines()
+            .filter_map(|line| line.split_once(" [label=").map(|(_, label)| label))
+            .collect();
+        pretty_assertions::assert_eq!(
+            labels,
+            vec![
+                r#""class"];"#,
+                r#""class", shape=box];"#,
+                r#""A", shape=box];"#,
+                r#""{", shape=box];"#,
+                r#""subroutineDec"];"#,
+                r#""function", shape=box];"#,
+                r#""void", shape=box];"#,
+                r#""f", shape=box];"#,
+                r#""(", shape=box];"#,
+                r#""parameterList"];"#,
+                r#"")", shape=box];"#,
+                r#""subroutineBody"];"#,
+                r#""{", shape=box];"#,
+                r#""statements"];"#,
+                r#""doStatement"];"#,
+                r#""do", shape=box];"#,
+                r#""g", shape=box];"#,
+                r#""(", shape=box];"#,
+                r#""expressionList / expression / term / x", shape=box];"#,
+                r#"")", shape=box];"#,
+                r#"";", shape=box];"#,
+                r#""returnStatement"];"#,
+                r#""return", shape=box];"#,
+                r#""expression"];"#,
+                r#""term / y", shape=box];"#,
+                r#""&lt;", shape=box];"#,
+                r#""term / a\\", shape=box];"#,
+                r#"";", shape=box];"#,
+                r#""}", shape=box];"#,
+                r#""}", shape=box];"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ast_to_dot() {
+        // Given
+        let class = crate::parser::parse_class(
+            "class A { field int x; method int f(int y) { if (~(y < 2)) { let x = 1 + y * 2; } return x; } }",
+        )
+        .unwrap();
+
+        // When
+        let dot = ast_to_dot(&class);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            dot,
+            r#"digraph ast {
+    ordering=out;
+    n0 [label="class A"];
+    n1 [label="field int x", shape=box];
+    n0 -> n1;
+    n2 [label="method int f(int y)"];
+    n3 [label="if"];
+    n4 [label="~"];
+    n5 [label="y", shape=box];
+    n6 [label="<"];
+    n7 [label="2", shape=box];
+    n6 -> n5;
+    n6 -> n7;
+    n4 -> n6;
+    n3 -> n4 [label="condition"];
+    n8 [label="let x"];
+    n9 [label="1", shape=box];
+    n10 [label="+"];
+    n11 [label="y", shape=box];
+    n10 -> n9;
+    n10 -> n11;
+    n12 [label="*"];
+    n13 [label="2", shape=box];
+    n12 -> n10;
+    n12 -> n13;
+    n8 -> n12;
+    n3 -> n8 [label="then"];
+    n2 -> n3;
+    n14 [label="return"];
+    n15 [label="x", shape=box];
+    n14 -> n15;
+    n2 -> n14;
+    n0 -> n2;
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,534 @@
+use std::{fmt, path::PathBuf};
+
+use crate::tokens::{Span, SpannedToken, Symbol, Token};
+
+/// An error raised while splitting the source code into tokens.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexError {
+    /// The source file couldn't be read.
+    Io { path: PathBuf, message: String },
+    /// A string constant isn't closed before the end of the input.
+    UnterminatedString { span: Span },
+    /// A string constant contains a line break.
+    NewlineInString { span: Span },
+    /// A character that can't start any token.
+    IllegalCharacter { character: char, span: Span },
+    /// An integer constant above 32767, the largest one allowed by Jack.
+    IntegerOutOfRange { value: String, span: Span },
+    /// An integer constant followed by letters, such as `12ab`.
+    MalformedIntegerConstant { value: String, span: Span },
+    /// A `/*` comment isn't closed before the end of the input.
+    UnterminatedBlockComment { span: Span },
+}
+
+impl LexError {
+    /// Returns the code identifying the kind of error in diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "E0001",
+            Self::UnterminatedString { .. } => "E0002",
+            Self::NewlineInString { .. } => "E0003",
+            Self::IllegalCharacter { .. } => "E0004",
+            Self::IntegerOutOfRange { .. } => "E0005",
+            Self::MalformedIntegerConstant { .. } => "E0006",
+            Self::UnterminatedBlockComment { .. } => "E0007",
+        }
+    }
+
+    /// Returns the location of the error, if it has one.
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            Self::Io { .. } => None,
+            Self::UnterminatedString { span }
+            | Self::NewlineInString { span }
+            | Self::IllegalCharacter { span, .. }
+            | Self::IntegerOutOfRange { span, .. }
+            | Self::MalformedIntegerConstant { span, .. }
+            | Self::UnterminatedBlockComment { span } => Some(span),
+        }
+    }
+
+    /// Returns the description of the error, without its location.
+    pub fn message(&self) -> String {
+        match self {
+            Self::Io { message, .. } => format!("failed to read file: {}", message),
+            Self::UnterminatedString { .. } => String::from("unterminated string constant"),
+            Self::NewlineInString { .. } => String::from("line break in string constant"),
+            Self::IllegalCharacter { character, .. } => {
+                format!("illegal character `{}`", character)
+            }
+            Self::IntegerOutOfRange { value, .. } => {
+                format!("integer constant `{}` is out of range (0..=32767)", value)
+            }
+            Self::MalformedIntegerConstant { value, .. } => {
+                format!("malformed integer constant `{}`", value)
+            }
+            Self::UnterminatedBlockComment { .. } => String::from("unterminated block comment"),
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, .. } => write!(f, "{}: {}", path.display(), self.message()),
+            _ => write_located(f, self.span(), &self.message()),
+        }
+    }
+}
+
+impl std::error::Error for LexError {}
+
+/// An error raised while parsing a stream of tokens into an AST.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// The parser found a token that doesn't fit the grammar.
+    UnexpectedToken {
+        expected: String,
+        found: Token<'static>,
+        span: Span,
+    },
+    /// The input ended while the parser expected more tokens.
+    UnexpectedEndOfInput { expected: String, span: Span },
+    /// A closing delimiter is missing, e.g. the `}` of a block.
+    UnclosedDelimiter {
+        expected: String,
+        /// The token found instead, `None` at the end of the input
+        found: Option<Token<'static>>,
+        span: Span,
+        /// The opening delimiter left unclosed
+        delimiter: Symbol,
+        opening: Box<Span>,
+    },
+    /// The input couldn't be tokenized.
+    Lex(LexError),
+}
+
+impl ParseError {
+    /// Returns the error for a token that isn't the expected one.
+    pub fn unexpected(expected: &str, found: &SpannedToken) -> Self {
+        Self::UnexpectedToken {
+            expected: expected.to_string(),
+            found: found.token.clone().into_owned(),
+            span: found.span.clone(),
+        }
+    }
+}
+
+impl From<LexError> for ParseError {
+    fn from(value: LexError) -> Self {
+        Self::Lex(value)
+    }
+}
+
+impl ParseError {
+    /// Returns the code identifying the kind of error in diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnexpectedToken { .. } => "E0100",
+            Self::UnexpectedEndOfInput { .. } => "E0101",
+            Self::UnclosedDelimiter { .. } => "E0102",
+            Self::Lex(e) => e.code(),
+        }
+    }
+
+    /// Returns the location of the error, if it has one.
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            Self::UnexpectedToken { span, .. }
+            | Self::UnexpectedEndOfInput { span, .. }
+            | Self::UnclosedDelimiter { span, .. } => Some(span),
+            Self::Lex(e) => e.span(),
+        }
+    }
+
+    /// Returns the description of the error, without its location.
+    pub fn message(&self) -> String {
+        match self {
+            Self::UnexpectedToken {
+                expected, found, ..
+            }
+            | Self::UnclosedDelimiter {
+                expected,
+                found: Some(found),
+                ..
+            } => format!("expected {}, found `{}`", expected, found),
+            Self::UnexpectedEndOfInput { expected, .. }
+            | Self::UnclosedDelimiter {
+                expected,
+                found: None,
+                ..
+            } => format!("expected {}, found end of input", expected),
+            Self::Lex(e) => e.message(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lex(e) => write!(f, "{}", e),
+            _ => write_located(f, self.span(), &self.message()),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// An error raised while generating VM code from an AST.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CodeGenError {
+    /// A variable is used without being declared in the subroutine or class.
+    UndeclaredVariable {
+        name: String,
+        subroutine: String,
+        span: Span,
+    },
+}
+
+impl CodeGenError {
+    /// Returns the code identifying the kind of error in diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UndeclaredVariable { .. } => "E0200",
+        }
+    }
+}
+
+impl fmt::Display for CodeGenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UndeclaredVariable {
+                name, subroutine, ..
+            } => {
+                write!(f, "undeclared variable `{}` in {}", name, subroutine)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CodeGenError {}
+
+/// An error found by the semantic checks of a class, such as a call to a
+/// subroutine that doesn't exist.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SemanticError {
+    /// A variable is used without being declared in the subroutine or class.
+    UndeclaredVariable { name: String, span: Span },
+    /// A subroutine is called on a class that isn't part of the program.
+    UnknownClass { name: String, span: Span },
+    /// A subroutine that the class doesn't declare is called.
+    UnknownSubroutine {
+        class: String,
+        name: String,
+        span: Span,
+        /// The declaration of the class, `None` for the OS classes
+        class_span: Option<Span>,
+    },
+    /// A subroutine is called with another number of arguments than its parameters.
+    ArgumentCount {
+        /// The full name of the subroutine (e.g. `Square.new`)
+        subroutine: String,
+        expected: usize,
+        found: usize,
+        span: Span,
+        declaration: Option<Span>,
+    },
+    /// `this` is used in a function, which has no object.
+    ThisInFunction { subroutine: String, span: Span },
+    /// A field is used in a function, which has no object to hold it.
+    FieldInFunction {
+        name: String,
+        subroutine: String,
+        span: Span,
+    },
+    /// A method is called without an object, e.g. `Square.draw()`.
+    MethodCalledAsFunction {
+        subroutine: String,
+        span: Span,
+        declaration: Option<Span>,
+    },
+    /// A variable is declared twice in the same scope, e.g. a parameter and
+    /// a local variable of the same name.
+    DuplicateDeclaration {
+        name: String,
+        span: Span,
+        /// The first declaration, which the name keeps
+        first: Span,
+    },
+}
+
+impl SemanticError {
+    /// Returns the code identifying the kind of error in diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UndeclaredVariable { .. } => "E0300",
+            Self::UnknownClass { .. } => "E0301",
+            Self::UnknownSubroutine { .. } => "E0302",
+            Self::ArgumentCount { .. } => "E0303",
+            Self::ThisInFunction { .. } => "E0304",
+            Self::MethodCalledAsFunction { .. } => "E0305",
+            Self::DuplicateDeclaration { .. } => "E0306",
+            Self::FieldInFunction { .. } => "E0307",
+        }
+    }
+
+    /// Returns the location of the error.
+    pub fn span(&self) -> &Span {
+        match self {
+            Self::UndeclaredVariable { span, .. }
+            | Self::UnknownClass { span, .. }
+            | Self::UnknownSubroutine { span, .. }
+            | Self::ArgumentCount { span, .. }
+            | Self::ThisInFunction { span, .. }
+            | Self::MethodCalledAsFunction { span, .. }
+            | Self::DuplicateDeclaration { span, .. }
+            | Self::FieldInFunction { span, .. } => span,
+        }
+    }
+
+    /// Returns the description of the error, without its location.
+    pub fn message(&self) -> String {
+        match self {
+            Self::UndeclaredVariable { name, .. } => format!("undeclared variable `{}`", name),
+            Self::UnknownClass { name, .. } => format!("unknown class `{}`", name),
+            Self::UnknownSubroutine { class, name, .. } => {
+                format!("no subroutine `{}` in class `{}`", name, class)
+            }
+            Self::ArgumentCount {
+                subroutine,
+                expected,
+                found,
+                ..
+            } => format!(
+                "`{}` takes {} argument{} but {} {} given",
+                subroutine,
+                expected,
+                if *expected == 1 { "" } else { "s" },
+                found,
+                if *found == 1 { "was" } else { "were" }
+            ),
+            Self::ThisInFunction { subroutine, .. } => {
+                format!("`this` used in function `{}`", subroutine)
+            }
+            Self::MethodCalledAsFunction { subroutine, .. } => {
+                format!("method `{}` called without an object", subroutine)
+            }
+            Self::DuplicateDeclaration { name, .. } => {
+                format!("`{}` is declared twice in the same scope", name)
+            }
+            Self::FieldInFunction {
+                name, subroutine, ..
+            } => format!("field `{}` used in function `{}`", name, subroutine),
+        }
+    }
+}
+
+impl fmt::Display for SemanticError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_located(f, Some(self.span()), &self.message())
+    }
+}
+
+impl std::error::Error for SemanticError {}
+
+/// A value used where its type doesn't fit, found by the type checker.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeError {
+    /// A value of another type than expected, e.g. a `boolean` assigned to
+    /// an `int` variable or passed as an `int` argument.
+    Mismatch {
+        expected: String,
+        found: String,
+        span: Span,
+    },
+    /// An operator applied to a value of a type it doesn't accept, e.g. `true + 1`.
+    InvalidOperand {
+        operator: Symbol,
+        /// The types the operator accepts (e.g. "`int`")
+        expected: String,
+        found: String,
+        span: Span,
+    },
+    /// A variable that isn't an `Array` is indexed.
+    NotAnArray {
+        name: String,
+        ty: String,
+        span: Span,
+    },
+    /// The result of a subroutine returning nothing is used as a value.
+    VoidValue { subroutine: String, span: Span },
+}
+
+impl TypeError {
+    /// Returns the code identifying the kind of error in diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Mismatch { .. } => "E0400",
+            Self::InvalidOperand { .. } => "E0401",
+            Self::NotAnArray { .. } => "E0402",
+            Self::VoidValue { .. } => "E0403",
+        }
+    }
+
+    /// Returns the location of the error.
+    pub fn span(&self) -> &Span {
+        match self {
+            Self::Mismatch { span, .. }
+            | Self::InvalidOperand { span, .. }
+            | Self::NotAnArray { span, .. }
+            | Self::VoidValue { span, .. } => span,
+        }
+    }
+
+    /// Returns the description of the error, without its location.
+    pub fn message(&self) -> String {
+        match self {
+            Self::Mismatch {
+                expected, found, ..
+            } => format!(
+                "mismatched types: expected `{}`, found `{}`",
+                expected, found
+            ),
+            Self::InvalidOperand {
+                operator,
+                expected,
+                found,
+                ..
+            } => format!(
+                "operator `{}` expects {}, found `{}`",
+                operator.to_str(),
+                expected,
+                found
+            ),
+            Self::NotAnArray { name, ty, .. } => {
+                format!("cannot index `{}` of type `{}`", name, ty)
+            }
+            Self::VoidValue { subroutine, .. } => {
+                format!("`{}` returns no value", subroutine)
+            }
+        }
+    }
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_located(f, Some(self.span()), &self.message())
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+/// A path through a subroutine that the VM can't run, found by the
+/// control-flow analysis.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlFlowError {
+    /// A path through the subroutine reaches its end without a `return`.
+    MissingReturn { subroutine: String, span: Span },
+    /// A bare `return;` in a subroutine returning a value.
+    MissingValue {
+        subroutine: String,
+        return_type: String,
+        span: Span,
+    },
+    /// A value returned from a `void` subroutine.
+    UnexpectedValue { subroutine: String, span: Span },
+    /// A statement after a statement that never completes, e.g. a `return`.
+    Unreachable {
+        span: Span,
+        /// The statement that never completes
+        after: Span,
+    },
+}
+
+impl ControlFlowError {
+    /// Returns the code identifying the kind of error in diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::MissingReturn { .. } => "E0500",
+            Self::MissingValue { .. } => "E0501",
+            Self::UnexpectedValue { .. } => "E0502",
+            Self::Unreachable { .. } => "E0503",
+        }
+    }
+
+    /// Returns the location of the error.
+    pub fn span(&self) -> &Span {
+        match self {
+            Self::MissingReturn { span, .. }
+            | Self::MissingValue { span, .. }
+            | Self::UnexpectedValue { span, .. }
+            | Self::Unreachable { span, .. } => span,
+        }
+    }
+
+    /// Returns the description of the error, without its location.
+    pub fn message(&self) -> String {
+        match self {
+            Self::MissingReturn { subroutine, .. } => {
+                format!("`{}` can reach its end without returning", subroutine)
+            }
+            Self::MissingValue {
+                subroutine,
+                return_type,
+                ..
+            } => format!(
+                "`{}` must return a value of type `{}`",
+                subroutine, return_type
+            ),
+            Self::UnexpectedValue { subroutine, .. } => {
+                format!("`{}` is void but returns a value", subroutine)
+            }
+            Self::Unreachable { .. } => String::from("unreachable statement"),
+        }
+    }
+}
+
+impl fmt::Display for ControlFlowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_located(f, Some(self.span()), &self.message())
+    }
+}
+
+impl std::error::Error for ControlFlowError {}
+
+/// An error raised while reading tokens or a parse tree back from JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonError {
+    /// The input isn't a JSON document of the expected shape.
+    Syntax { message: String },
+    /// The document was written with another version of the schema.
+    UnsupportedVersion { version: u32 },
+    /// A token whose kind and text don't make a Jack token.
+    InvalidToken { kind: String, text: String },
+    /// A node whose kind isn't a rule of the grammar.
+    UnknownNode { kind: String },
+    /// A token attribute that the extended parse tree doesn't have.
+    UnknownAttribute { name: String },
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax { message } => write!(f, "invalid JSON: {}", message),
+            Self::UnsupportedVersion { version } => {
+                write!(f, "unsupported schema version {}", version)
+            }
+            Self::InvalidToken { kind, text } => {
+                write!(f, "invalid {} token `{}`", kind, text)
+            }
+            Self::UnknownNode { kind } => write!(f, "unknown node `{}`", kind),
+            Self::UnknownAttribute { name } => write!(f, "unknown attribute `{}`", name),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+/// Writes the message prefixed with its location, if any.
+fn write_located(f: &mut fmt::Formatter<'_>, span: Option<&Span>, message: &str) -> fmt::Result {
+    match span {
+        Some(span) => write!(f, "{}: {}", span, message),
+        None => write!(f, "{}", message),
+    }
+}
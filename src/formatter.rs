@@ -0,0 +1,364 @@
+use crate::{
+    compilation_engine::CompilationEngine,
+    cst::{Cst, CstElement, CstNode, CstToken},
+    errors::ParseError,
+    tokenizer::JackTokenizer,
+    tokens::{Symbol, Token, Trivia, TriviaKind},
+};
+
+/// Number of spaces added for each level of nesting.
+const INDENT_WIDTH: usize = 4;
+
+/// Parses the source with its comments and returns it in the canonical layout.
+pub fn format_source(source: String) -> Result<String, ParseError> {
+    let tokenizer = JackTokenizer::from_source_lossless(source)?;
+    Ok(format(&CompilationEngine::new(tokenizer).compile_tree()?))
+}
+
+/// Prints the tree in the canonical layout:
+/// - one declaration or statement per line, indented by nesting level,
+/// - opening braces at the end of the line, closing braces on their own,
+/// - spaces around binary operators and after commas,
+/// - a blank line between subroutines, at most one blank line elsewhere.
+///
+/// Comments are kept, on their own line or at the end of the line they
+/// were on.
+pub fn format(cst: &Cst) -> String {
+    let mut formatter = Formatter::default();
+    formatter.node(&cst.root);
+    formatter.comments(&cst.trailing_trivia);
+    formatter.end_line();
+    let mut output = formatter.lines.join("\n");
+    output.push('\n');
+    output
+}
+
+#[derive(Debug, Default)]
+struct Formatter {
+    /// The lines written so far, without their line break
+    lines: Vec<String>,
+    /// The line being written, without its indentation
+    line: String,
+    /// The nesting level of the line being written
+    indent: usize,
+    /// The last token written, deciding the spacing of the next one
+    previous: Option<Token<'static>>,
+    /// Whether the last token written is a unary operator
+    after_unary_op: bool,
+    /// Whether a blank line goes before the next line started. It is added
+    /// when the line starts, so that comments following the previous token
+    /// on the same line are kept before it.
+    blank_line_pending: bool,
+}
+
+impl Formatter {
+    fn node(&mut self, node: &CstNode) {
+        let mut previous_child: Option<&CstElement> = None;
+        for child in &node.children {
+            match child {
+                CstElement::Token(token) => self.token(token, false),
+                CstElement::Node(child) => {
+                    if child.kind == "subroutineDec"
+                        && matches!(previous_child, Some(CstElement::Node(_)))
+                    {
+                        self.blank_line();
+                    }
+                    self.child(child)
+                }
+            }
+            previous_child = Some(child);
+        }
+    }
+
+    fn child(&mut self, node: &CstNode) {
+        match node.kind {
+            "classVarDec" | "subroutineDec" | "varDec" | "letStatement" | "ifStatement"
+            | "whileStatement" | "doStatement" | "returnStatement" => {
+                self.node(node);
+                self.end_line();
+            }
+            "term" => match node.children.first() {
+                Some(CstElement::Token(op)) if is_unary_op(node, op) => {
+                    self.token(op, true);
+                    node.children[1..].iter().for_each(|child| match child {
+                        CstElement::Node(child) => self.child(child),
+                        CstElement::Token(token) => self.token(token, false),
+                    });
+                }
+                _ => self.node(node),
+            },
+            _ => self.node(node),
+        }
+    }
+
+    fn token(&mut self, token: &CstToken, unary_op: bool) {
+        let is_closing = token.token.token == Token::Symbol(Symbol::CurlRight);
+        self.leading_trivia(&token.leading_trivia, !is_closing);
+        if is_closing {
+            self.end_line();
+            self.indent = self.indent.saturating_sub(1);
+        }
+
+        if self.needs_space(&token.token.token) {
+            self.line.push(' ');
+        }
+        self.write(&token.text);
+        self.previous = Some(token.token.token.clone());
+        self.after_unary_op = unary_op;
+
+        if token.token.token == Token::Symbol(Symbol::CurlLeft) {
+            self.end_line();
+            self.indent += 1;
+        }
+    }
+
+    /// Writes the comments preceding a token. A blank line found before a
+    /// token starting a line is kept if `keep_blank_line` is set.
+    fn leading_trivia(&mut self, trivia: &[Trivia], keep_blank_line: bool) {
+        let comments = self.comments(trivia);
+        if keep_blank_line && comments.blank_line_after && self.line.is_empty() {
+            self.blank_line();
+        }
+    }
+
+    /// Writes the comments of the trivia and returns whether the last one is
+    /// followed by a blank line.
+    fn comments(&mut self, trivia: &[Trivia]) -> Comments {
+        // A blank line found in the trivia takes the place of the pending one
+        if trivia
+            .iter()
+            .any(|t| t.kind == TriviaKind::Whitespace && t.text.matches('\n').count() > 1)
+        {
+            self.blank_line_pending = false;
+        }
+
+        let mut line_breaks = 0;
+        for t in trivia {
+            if t.kind == TriviaKind::Whitespace {
+                line_breaks += t.text.matches('\n').count();
+                continue;
+            }
+
+            let text = t.text.trim_end();
+            if line_breaks == 0 && !self.is_line_empty() {
+                // A comment following code on the same line stays there
+                self.append_to_last_line(text);
+            } else {
+                if line_breaks > 1 {
+                    self.blank_line();
+                }
+                self.end_line();
+                self.write_comment(text);
+            }
+            if t.kind == TriviaKind::LineComment {
+                self.end_line();
+            }
+            line_breaks = 0;
+        }
+        Comments {
+            blank_line_after: line_breaks > 1,
+        }
+    }
+
+    /// Writes a comment on its own line, aligning the `*` of its following lines.
+    fn write_comment(&mut self, text: &str) {
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let line = match (i, line.starts_with('*')) {
+                (0, _) => line.to_string(),
+                (_, true) => format!(" {}", line),
+                (_, false) => format!("   {}", line),
+            };
+            self.write(line.trim_end());
+            self.end_line();
+        }
+        self.previous = None;
+    }
+
+    fn needs_space(&self, next: &Token) -> bool {
+        let Some(previous) = &self.previous else {
+            return false;
+        };
+        if self.line.is_empty() || self.after_unary_op {
+            return false;
+        }
+        let no_space_before = matches!(
+            next,
+            Token::Symbol(
+                Symbol::Comma
+                    | Symbol::Semicolon
+                    | Symbol::ParenthesisRight
+                    | Symbol::SquareBracketRight
+                    | Symbol::Dot
+            )
+        );
+        let no_space_after = matches!(
+            previous,
+            Token::Symbol(Symbol::ParenthesisLeft | Symbol::SquareBracketLeft | Symbol::Dot)
+        );
+        // Calls and array accesses, e.g. `f(x)` and `a[i]`
+        let is_call_or_index = matches!(previous, Token::Identifier(_))
+            && matches!(
+                next,
+                Token::Symbol(Symbol::ParenthesisLeft | Symbol::SquareBracketLeft)
+            );
+        !(no_space_before || no_space_after || is_call_or_index)
+    }
+
+    /// Appends a comment to the line being written, or to the last line
+    /// written if it was just ended.
+    fn append_to_last_line(&mut self, comment: &str) {
+        match self.line.is_empty() {
+            true => {
+                let last = self.lines.last_mut().expect("a line was written");
+                *last += " ";
+                *last += comment;
+            }
+            false => {
+                self.line += " ";
+                self.line += comment;
+            }
+        }
+    }
+
+    /// Whether no code precedes the current position on its line, which is
+    /// the case at the start of the file and after a blank line.
+    fn is_line_empty(&self) -> bool {
+        self.line.is_empty()
+            && self
+                .lines
+                .last()
+                .map(|line| line.is_empty())
+                .unwrap_or(true)
+    }
+
+    /// Writes the text to the line, starting it with the pending blank line
+    /// unless it follows an opening brace or the start of the file.
+    fn write(&mut self, text: &str) {
+        if self.line.is_empty() && std::mem::take(&mut self.blank_line_pending) {
+            match self.lines.last() {
+                Some(line) if !line.is_empty() && !line.ends_with('{') => {
+                    self.lines.push(String::new())
+                }
+                _ => {}
+            }
+        }
+        self.line += text;
+    }
+
+    fn end_line(&mut self) {
+        if self.line.is_empty() {
+            return;
+        }
+        let line = std::mem::take(&mut self.line);
+        self.lines
+            .push(" ".repeat(self.indent * INDENT_WIDTH) + &line);
+    }
+
+    /// Ends the line and adds a blank line before the next one.
+    fn blank_line(&mut self) {
+        self.end_line();
+        self.blank_line_pending = true;
+    }
+}
+
+/// What follows the comments of a trivia.
+struct Comments {
+    blank_line_after: bool,
+}
+
+/// Whether the token starting the term is a unary operator (e.g. `-x`, `~done`).
+fn is_unary_op(term: &CstNode, token: &CstToken) -> bool {
+    term.children.len() == 2
+        && matches!(
+            token.token.token,
+            Token::Symbol(Symbol::Minus | Symbol::Tilte)
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_source() {
+        // Given
+        let source = r"class Main{field int x,y;// position
+static boolean done ;
+  /** Runs. */
+function void main ( int a,int b ) {var int i ;
+let x=-a+(b*2) ;let y [ i ]=~done;
+
+
+if(x<y){do Output.printInt ( x );}else{return;}
+while (~(i = 0)) { let i = i - 1; /* step */ }
+    return ;}
+method void f() { return; } }";
+
+        // When
+        let formatted = format_source(source.to_string()).unwrap();
+
+        // Then
+        pretty_assertions::assert_eq!(
+            formatted,
+            r"class Main {
+    field int x, y; // position
+    static boolean done;
+
+    /** Runs. */
+    function void main(int a, int b) {
+        var int i;
+        let x = -a + (b * 2);
+        let y[i] = ~done;
+
+        if (x < y) {
+            do Output.printInt(x);
+        } else {
+            return;
+        }
+        while (~(i = 0)) {
+            let i = i - 1; /* step */
+        }
+        return;
+    }
+
+    method void f() {
+        return;
+    }
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_format_comments() {
+        // Given
+        let source = "// header\n\n\n/**\n   * Doc.\n      */\nclass Main { // the class\n  // alone\n\n\n  function void f() { return; }\n}\n// end";
+
+        // When
+        let formatted = format_source(source.to_string()).unwrap();
+
+        // Then
+        pretty_assertions::assert_eq!(
+            formatted,
+            "// header\n\n/**\n * Doc.\n */\nclass Main { // the class\n    // alone\n\n    function void f() {\n        return;\n    }\n}\n// end\n"
+        );
+    }
+
+    #[test]
+    fn test_format_idempotent() {
+        // Given
+        let source = "class A { field int x; // x\n // y\n\n\n method int g() {\n let x = 1 + // one\n 2; return x; } }";
+
+        // When
+        let formatted = format_source(source.to_string()).unwrap();
+
+        // Then
+        assert_eq!(format_source(formatted.clone()).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_format_invalid_source() {
+        assert!(format_source(String::from("class Main { let }")).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+/// Returns the number of jobs to run by default, one per available CPU.
+pub fn default_jobs() -> NonZeroUsize {
+    std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN)
+}
+
+/// Applies `f` to the items on up to `jobs` threads and returns the results
+/// in the order of the items, whatever the order they finish in.
+pub fn map<T, R, F>(items: &[T], jobs: NonZeroUsize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let jobs = jobs.get().min(items.len());
+    if jobs <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(i) else {
+                    break;
+                };
+                let result = f(item);
+                results.lock().expect("a job panicked")[i] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .expect("a job panicked")
+        .into_iter()
+        .map(|result| result.expect("all the items are mapped"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_keeps_order() {
+        // Given
+        let items: Vec<u64> = (0..50).collect();
+
+        // When
+        let results = map(&items, NonZeroUsize::new(4).unwrap(), |i| {
+            // Finish the first items last
+            std::thread::sleep(std::time::Duration::from_micros(50 - i));
+            i * 2
+        });
+
+        // Then
+        pretty_assertions::assert_eq!(results, (0..50).map(|i| i * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_map_single_job() {
+        // Given
+        let items = vec!["a", "b"];
+
+        // When
+        let results = map(&items, NonZeroUsize::MIN, |s| s.to_uppercase());
+
+        // Then
+        assert_eq!(results, vec!["A", "B"]);
+    }
+
+    #[test]
+    fn test_map_empty() {
+        let results: Vec<u8> = map(&[], default_jobs(), |i: &u8| *i);
+        assert!(results.is_empty());
+    }
+}
@@ -0,0 +1,462 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cst::{Cst, CstElement, CstNode, CstToken},
+    errors::JsonError,
+    tokens::{Keyword, Span, SpannedToken, Symbol, Token},
+};
+
+/// The version of the schema written by this crate, carried by the
+/// documents. It changes only when a field is removed or changes meaning.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The kinds of the nodes of the parse tree, one per grammar rule.
+const NODE_KINDS: [&str; 15] = [
+    "class",
+    "classVarDec",
+    "subroutineDec",
+    "parameterList",
+    "subroutineBody",
+    "varDec",
+    "statements",
+    "letStatement",
+    "ifStatement",
+    "whileStatement",
+    "doStatement",
+    "returnStatement",
+    "expression",
+    "term",
+    "expressionList",
+];
+
+/// The names of the attributes of the tokens of the extended parse tree.
+const ATTRIBUTE_NAMES: [&str; 3] = ["category", "index", "usage"];
+
+/// The tokens of a file, and the `file` they were read from or `null`:
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "file": "Main.jack",
+///   "tokens": [
+///     {
+///       "kind": "keyword",
+///       "text": "class",
+///       "span": { "start": 0, "end": 5, "line": 1, "column": 1 }
+///     }
+///   ]
+/// }
+/// ```
+///
+/// A token's `kind` is the name of its XML element: `keyword`, `symbol`,
+/// `identifier`, `integerConstant` or `stringConstant`. Its `text` is the
+/// unescaped content of the element: the decimal value of an integer
+/// constant and a string constant without its quotes. The `span` holds the
+/// byte offsets of the token in the file and the line and column of its
+/// first character, starting at 1 and counting columns in characters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonTokens {
+    pub version: u32,
+    pub file: Option<String>,
+    pub tokens: Vec<JsonToken>,
+}
+
+impl JsonTokens {
+    pub fn new(tokens: &[SpannedToken]) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            file: file(tokens.first().map(|token| &token.span)),
+            tokens: tokens.iter().map(JsonToken::new).collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        to_json(self)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, JsonError> {
+        let tokens: Self = from_json(json)?;
+        check_version(tokens.version)?;
+        Ok(tokens)
+    }
+
+    /// Returns the tokens XML, as written by `JackTokenizer::to_xml`.
+    pub fn to_xml(&self) -> Result<String, JsonError> {
+        let mut acc = String::from("<tokens>\n");
+        for token in &self.tokens {
+            acc += &(token.to_token()?.to_xml_element() + "\n");
+        }
+        Ok(acc + "</tokens>\n")
+    }
+}
+
+/// The parse tree of a file, and the `file` it was read from or `null`:
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "file": "Main.jack",
+///   "root": {
+///     "kind": "class",
+///     "children": [
+///       { "type": "token", "kind": "keyword", "text": "class", "span": { ... } },
+///       { "type": "node", "kind": "classVarDec", "children": [ ... ] }
+///     ]
+///   }
+/// }
+/// ```
+///
+/// Nodes are named after their grammar rule, as the elements of the parse
+/// tree XML. The `type` of the children of a node tells nodes from tokens,
+/// and the `root` is always the `class` node. The tokens are written as in
+/// `JsonTokens`, and those of the extended parse tree have an `attributes`
+/// object (e.g. `{ "category": "var", "index": "0", "usage": "used" }`),
+/// which is left out when empty.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonTree {
+    pub version: u32,
+    pub file: Option<String>,
+    pub root: JsonNode,
+}
+
+impl JsonTree {
+    pub fn new(cst: &Cst) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            file: file(cst.root.tokens().first().map(|token| &token.token.span)),
+            root: JsonNode::new(&cst.root),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        to_json(self)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, JsonError> {
+        let tree: Self = from_json(json)?;
+        check_version(tree.version)?;
+        Ok(tree)
+    }
+
+    /// Returns the parse tree XML, as written by `Cst::to_xml`.
+    pub fn to_xml(&self) -> Result<String, JsonError> {
+        Ok(self.to_cst()?.to_xml())
+    }
+
+    /// Returns the parse tree described by the document, without trivia.
+    pub fn to_cst(&self) -> Result<Cst, JsonError> {
+        Ok(Cst {
+            root: self.root.to_cst()?,
+            trailing_trivia: Vec::new(),
+        })
+    }
+}
+
+/// A child of a node of the parse tree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum JsonElement {
+    Node(JsonNode),
+    Token(JsonToken),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonNode {
+    pub kind: String,
+    pub children: Vec<JsonElement>,
+}
+
+impl JsonNode {
+    fn new(node: &CstNode) -> Self {
+        let children = node
+            .children
+            .iter()
+            .map(|child| match child {
+                CstElement::Node(node) => JsonElement::Node(Self::new(node)),
+                CstElement::Token(token) => JsonElement::Token(JsonToken::from_cst(token)),
+            })
+            .collect();
+        Self {
+            kind: node.kind.to_string(),
+            children,
+        }
+    }
+
+    fn to_cst(&self) -> Result<CstNode, JsonError> {
+        let kind = known(&NODE_KINDS, &self.kind).ok_or_else(|| JsonError::UnknownNode {
+            kind: self.kind.clone(),
+        })?;
+        let mut node = CstNode::new(kind);
+        for child in &self.children {
+            node.children.push(match child {
+                JsonElement::Node(child) => CstElement::Node(child.to_cst()?),
+                JsonElement::Token(token) => CstElement::Token(token.to_cst()?),
+            });
+        }
+        Ok(node)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonToken {
+    pub kind: String,
+    pub text: String,
+    pub span: JsonSpan,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl JsonToken {
+    fn new(token: &SpannedToken) -> Self {
+        let kind = token.token.start_xml();
+        Self {
+            kind: kind
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .to_string(),
+            text: match &token.token {
+                Token::Symbol(s) => s.to_str().to_string(),
+                token => token.to_xml(),
+            },
+            span: JsonSpan::from(&token.span),
+            attributes: BTreeMap::new(),
+        }
+    }
+
+    fn from_cst(token: &CstToken) -> Self {
+        Self {
+            attributes: token
+                .attributes
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect(),
+            ..Self::new(&token.token)
+        }
+    }
+
+    fn to_cst(&self) -> Result<CstToken, JsonError> {
+        let token = self.to_token()?;
+        let attributes = self
+            .attributes
+            .iter()
+            .map(|(name, value)| match known(&ATTRIBUTE_NAMES, name) {
+                Some(name) => Ok((name, value.clone())),
+                None => Err(JsonError::UnknownAttribute { name: name.clone() }),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(CstToken {
+            text: token.to_string(),
+            token: SpannedToken {
+                token,
+                span: self.span.to_span(),
+            },
+            leading_trivia: Vec::new(),
+            attributes,
+        })
+    }
+
+    /// Returns the Jack token described by the kind and text.
+    pub fn to_token(&self) -> Result<Token<'static>, JsonError> {
+        let invalid = || JsonError::InvalidToken {
+            kind: self.kind.clone(),
+            text: self.text.clone(),
+        };
+        let text = self.text.as_str();
+        Ok(match self.kind.as_str() {
+            "keyword" => Token::Keyword(Keyword::try_from(text).map_err(|_| invalid())?),
+            "symbol" => {
+                let mut chars = text.chars();
+                match (chars.next().map(Symbol::try_from), chars.next()) {
+                    (Some(Ok(symbol)), None) => Token::Symbol(symbol),
+                    _ => return Err(invalid()),
+                }
+            }
+            "identifier" => Token::Identifier(text.to_string().into()),
+            "integerConstant" => Token::IntConst(text.parse().map_err(|_| invalid())?),
+            "stringConstant" => Token::StringConst(text.to_string().into()),
+            _ => return Err(invalid()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JsonSpan {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl JsonSpan {
+    /// Returns the span, which doesn't know its file.
+    fn to_span(&self) -> Span {
+        Span {
+            file: None,
+            start: self.start,
+            end: self.end,
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+impl From<&Span> for JsonSpan {
+    fn from(span: &Span) -> Self {
+        Self {
+            start: span.start,
+            end: span.end,
+            line: span.line,
+            column: span.column,
+        }
+    }
+}
+
+fn file(span: Option<&Span>) -> Option<String> {
+    span.and_then(|span| span.file.as_ref())
+        .map(|file| file.display().to_string())
+}
+
+/// Returns the static copy of the name from the list, as a `Cst` only holds
+/// static names.
+fn known(names: &[&'static str], name: &str) -> Option<&'static str> {
+    names.iter().copied().find(|known| *known == name)
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    let mut json = serde_json::to_string_pretty(value).expect("the documents are valid JSON");
+    json.push('\n');
+    json
+}
+
+fn from_json<'a, T: Deserialize<'a>>(json: &'a str) -> Result<T, JsonError> {
+    serde_json::from_str(json).map_err(|e| JsonError::Syntax {
+        message: e.to_string(),
+    })
+}
+
+fn check_version(version: u32) -> Result<(), JsonError> {
+    match version {
+        SCHEMA_VERSION => Ok(()),
+        version => Err(JsonError::UnsupportedVersion { version }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compilation_engine::CompilationEngine, tokenizer::JackTokenizer};
+
+    #[test]
+    fn test_tokens_to_json() {
+        // Given
+        let tokenizer = JackTokenizer::from_source(String::from("let s = \"a<b\";")).unwrap();
+
+        // When
+        let json = JsonTokens::new(tokenizer.tokens()).to_json();
+
+        // Then
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        pretty_assertions::assert_eq!(
+            value,
+            serde_json::json!({
+                "version": 1,
+                "file": null,
+                "tokens": [
+                    { "kind": "keyword", "text": "let", "span": { "start": 0, "end": 3, "line": 1, "column": 1 } },
+                    { "kind": "identifier", "text": "s", "span": { "start": 4, "end": 5, "line": 1, "column": 5 } },
+                    { "kind": "symbol", "text": "=", "span": { "start": 6, "end": 7, "line": 1, "column": 7 } },
+                    { "kind": "stringConstant", "text": "a<b", "span": { "start": 8, "end": 13, "line": 1, "column": 9 } },
+                    { "kind": "symbol", "text": ";", "span": { "start": 13, "end": 14, "line": 1, "column": 14 } },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_tree_to_json() {
+        // Given
+        let tokenizer =
+            JackTokenizer::from_source(String::from("class A { field int x; }")).unwrap();
+        let cst = CompilationEngine::extended(tokenizer)
+            .compile_tree()
+            .unwrap();
+
+        // When
+        let json = JsonTree::new(&cst).to_json();
+
+        // Then
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let class_var_dec = &value["root"]["children"][3];
+        assert_eq!(class_var_dec["type"], "node");
+        assert_eq!(value["root"]["kind"], "class");
+        assert_eq!(class_var_dec["kind"], "classVarDec");
+        pretty_assertions::assert_eq!(
+            class_var_dec["children"][2],
+            serde_json::json!({
+                "type": "token",
+                "kind": "identifier",
+                "text": "x",
+                "span": { "start": 20, "end": 21, "line": 1, "column": 21 },
+                "attributes": { "category": "field", "index": "0", "usage": "declared" }
+            })
+        );
+        assert!(class_var_dec["children"][0].get("attributes").is_none());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // Given
+        let source = "class A { method int f(int a) { return a < 1 & \"&\"; } }";
+        let tokenizer = JackTokenizer::from_source(String::from(source)).unwrap();
+        let cst = CompilationEngine::extended(tokenizer.clone())
+            .compile_tree()
+            .unwrap();
+
+        // When
+        let tokens = JsonTokens::from_json(&JsonTokens::new(tokenizer.tokens()).to_json()).unwrap();
+        let tree = JsonTree::from_json(&JsonTree::new(&cst).to_json()).unwrap();
+
+        // Then
+        pretty_assertions::assert_eq!(tokens.to_xml().unwrap(), tokenizer.to_xml());
+        pretty_assertions::assert_eq!(tree.to_xml().unwrap(), cst.to_xml());
+    }
+
+    #[test]
+    fn test_from_json_errors() {
+        // Given
+        let invalid_token = r#"{"version": 1, "file": null, "tokens": [
+            {"kind": "symbol", "text": "<=", "span": {"start": 0, "end": 2, "line": 1, "column": 1}}
+        ]}"#;
+
+        // When
+        let syntax = JsonTokens::from_json("{\"version\": 1}");
+        let version = JsonTree::from_json(
+            r#"{"version": 2, "file": null, "root": {"type": "node", "kind": "class", "children": []}}"#,
+        );
+        let token = JsonTokens::from_json(invalid_token).unwrap().to_xml();
+        let node = JsonTree::from_json(
+            r#"{"version": 1, "file": null, "root": {"type": "node", "kind": "klass", "children": []}}"#,
+        )
+        .unwrap()
+        .to_xml();
+
+        // Then
+        assert!(matches!(syntax, Err(JsonError::Syntax { .. })));
+        assert_eq!(version, Err(JsonError::UnsupportedVersion { version: 2 }));
+        assert_eq!(
+            token,
+            Err(JsonError::InvalidToken {
+                kind: String::from("symbol"),
+                text: String::from("<="),
+            })
+        );
+        assert_eq!(
+            node,
+            Err(JsonError::UnknownNode {
+                kind: String::from("klass"),
+            })
+        );
+    }
+}
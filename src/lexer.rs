@@ -0,0 +1,695 @@
+use std::{borrow::Cow, path::Path, sync::Arc};
+
+use crate::{
+    errors::LexError,
+    tokens::{Keyword, Span, SpannedToken, Symbol, Token, Trivia, TriviaKind},
+};
+
+/// The largest integer constant allowed by the Jack grammar.
+pub const MAX_INT_CONST: u16 = 32767;
+
+/// The location of a character in the source, used to build spans.
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+/// Splits Jack source code into tokens, borrowing the identifiers and
+/// string constants from the source instead of copying them.
+/// Whitespace and comments are skipped, unless read with `leading_trivia`
+/// before each token. The iteration stops after the first error.
+#[derive(Debug, Clone)]
+pub struct Lexer<'src> {
+    /// The source code being tokenized
+    source: &'src str,
+    /// The file the source was read from, if any
+    file: Option<Arc<Path>>,
+    /// The byte offset of the next character to read
+    offset: usize,
+    /// The line of the next character to read, starting at 1
+    line: usize,
+    /// The number of characters read on the current line
+    column: usize,
+    /// Whether an error was returned, ending the iteration
+    failed: bool,
+}
+
+impl<'src> Lexer<'src> {
+    pub fn new(source: &'src str) -> Self {
+        Self {
+            source,
+            file: None,
+            offset: 0,
+            line: 1,
+            column: 0,
+            failed: false,
+        }
+    }
+
+    /// Records the file in the span of each token.
+    pub fn with_file(mut self, file: Option<Arc<Path>>) -> Self {
+        self.file = file;
+        self
+    }
+
+    /// Returns the source code being tokenized.
+    pub fn source(&self) -> &'src str {
+        self.source
+    }
+
+    /// Returns the span right after the last character read, which is
+    /// the end of the input once the iteration is over.
+    pub fn end_span(&self) -> Span {
+        let position = self.current_position();
+        self.span(position, position.offset)
+    }
+
+    /// Returns the next token along with its location.
+    /// This is done by skipping the whitespace and comments,
+    /// then handling 4 cases on the next character:
+    /// 1. char is a symbol: it is a token on its own.
+    /// 2. char is a quote: we take the chars up
+    ///    until we reach the next quote.
+    /// 3. char is a digit: we read the integer
+    ///    constant up to its last digit.
+    /// 4. char is a letter or _: we read the word
+    ///    and check if it is a keyword or an identifier.
+    ///
+    /// Any other character is illegal.
+    pub fn next_spanned(&mut self) -> Option<Result<SpannedToken<'src>, LexError>> {
+        if self.failed {
+            return None;
+        }
+        let result = self.lex_token().transpose()?;
+        self.failed = result.is_err();
+        Some(result)
+    }
+
+    fn lex_token(&mut self) -> Result<Option<SpannedToken<'src>>, LexError> {
+        self.skip_trivia()?;
+        let start = self.current_position();
+        let Some(c) = self.peek() else {
+            return Ok(None);
+        };
+
+        let token = if let Ok(symbol) = Symbol::try_from(c) {
+            self.bump();
+            Token::Symbol(symbol)
+        } else if c == '"' {
+            self.lex_string(start)?
+        } else if c.is_ascii_digit() {
+            // The literal runs up to the next delimiter so that
+            // letters glued to the digits are reported with it
+            let literal = self.eat_word();
+            Self::int_const_token(literal, self.span(start, self.offset))?
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let word = self.eat_word();
+            match Keyword::try_from(word) {
+                Ok(keyword) => Token::Keyword(keyword),
+                Err(_) => Token::Identifier(Cow::Borrowed(word)),
+            }
+        } else {
+            self.bump();
+            return Err(LexError::IllegalCharacter {
+                character: c,
+                span: self.span(start, self.offset),
+            });
+        };
+
+        Ok(Some(SpannedToken {
+            token,
+            span: self.span(start, self.offset),
+        }))
+    }
+
+    /// Reads the whitespace and comments up to the next token, in source order.
+    /// Once all the tokens are read, returns the ones ending the input.
+    pub fn leading_trivia(&mut self) -> Result<Vec<Trivia<'src>>, LexError> {
+        let mut trivia = Vec::new();
+        while let Some(t) = self.lex_trivia().inspect_err(|_| self.failed = true)? {
+            trivia.push(t);
+        }
+        Ok(trivia)
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), LexError> {
+        while self.lex_trivia()?.is_some() {}
+        Ok(())
+    }
+
+    /// Reads a run of whitespace or one of the 3 types of comments:
+    /// - Inlined comments in the form of //
+    /// - API comments starting with "/**" and ending with "*/".
+    /// - Range comments starting with "/*" and ending with "*/".
+    fn lex_trivia(&mut self) -> Result<Option<Trivia<'src>>, LexError> {
+        let start = self.current_position();
+        let rest = self.rest();
+        let (kind, len) = if rest.starts_with(char::is_whitespace) {
+            let len = rest
+                .find(|c: char| !c.is_whitespace())
+                .unwrap_or(rest.len());
+            (TriviaKind::Whitespace, len)
+        } else if rest.starts_with("//") {
+            (
+                TriviaKind::LineComment,
+                rest.find('\n').unwrap_or(rest.len()),
+            )
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            let Some(end) = comment.find("*/") else {
+                return Err(LexError::UnterminatedBlockComment {
+                    span: self.span(start, self.source.len()),
+                });
+            };
+            // "/**/" is an empty range comment
+            let kind = match comment.starts_with('*') && end > 0 {
+                true => TriviaKind::DocComment,
+                false => TriviaKind::BlockComment,
+            };
+            (kind, 2 + end + 2)
+        } else {
+            return Ok(None);
+        };
+
+        self.bump_to(self.offset + len);
+        Ok(Some(Trivia {
+            kind,
+            text: Cow::Borrowed(&rest[..len]),
+            span: self.span(start, self.offset),
+        }))
+    }
+
+    /// Reads a string constant, which can't contain a quote or a line break.
+    fn lex_string(&mut self, start: Position) -> Result<Token<'src>, LexError> {
+        let content_start = self.offset + 1;
+        let rest = &self.source[content_start..];
+        match rest.find(['"', '\n', '\r']) {
+            Some(end) if rest.as_bytes()[end] == b'"' => {
+                // we include the 2 quotes
+                self.bump_to(content_start + end + 1);
+                Ok(Token::StringConst(Cow::Borrowed(&rest[..end])))
+            }
+            Some(end) => Err(LexError::NewlineInString {
+                span: self.span(start, content_start + end),
+            }),
+            None => Err(LexError::UnterminatedString {
+                span: self.span(start, self.source.len()),
+            }),
+        }
+    }
+
+    /// Reads the ascii letters, digits and _ starting at the current character.
+    fn eat_word(&mut self) -> &'src str {
+        let rest = self.rest();
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        self.bump_to(self.offset + end);
+        &rest[..end]
+    }
+
+    /// Converts a literal starting with a digit to an integer constant
+    /// in the 0..=32767 range.
+    fn int_const_token(literal: &str, span: Span) -> Result<Token<'src>, LexError> {
+        if !literal.chars().all(|c| c.is_ascii_digit()) {
+            return Err(LexError::MalformedIntegerConstant {
+                value: literal.to_string(),
+                span,
+            });
+        }
+        // Overflowing u16 is out of range as well
+        match str::parse::<u16>(literal) {
+            Ok(value) if value <= MAX_INT_CONST => Ok(Token::IntConst(value)),
+            _ => Err(LexError::IntegerOutOfRange {
+                value: literal.to_string(),
+                span,
+            }),
+        }
+    }
+
+    fn rest(&self) -> &'src str {
+        &self.source[self.offset..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            self.bump_to(self.offset + c.len_utf8());
+        }
+    }
+
+    /// Moves to the given byte offset, keeping track of the line breaks and
+    /// the characters passed.
+    fn bump_to(&mut self, offset: usize) {
+        let passed = &self.source[self.offset..offset];
+        match passed.rfind('\n') {
+            Some(last_line_break) => {
+                self.line += passed.matches('\n').count();
+                self.column = passed[last_line_break + 1..].chars().count();
+            }
+            None => self.column += passed.chars().count(),
+        }
+        self.offset = offset;
+    }
+
+    fn current_position(&self) -> Position {
+        Position {
+            offset: self.offset,
+            line: self.line,
+            column: self.column + 1,
+        }
+    }
+
+    fn span(&self, start: Position, end: usize) -> Span {
+        Span {
+            file: self.file.clone(),
+            start: start.offset,
+            end,
+            line: start.line,
+            column: start.column,
+        }
+    }
+}
+
+impl<'src> Iterator for Lexer<'src> {
+    type Item = Result<Token<'src>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_spanned()
+            .map(|result| result.map(|spanned| spanned.token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(input: &str) -> Vec<Token<'_>> {
+        Lexer::new(input).collect::<Result<_, _>>().unwrap()
+    }
+
+    fn spanned_tokens(input: &str) -> Vec<SpannedToken<'_>> {
+        let mut lexer = Lexer::new(input);
+        std::iter::from_fn(|| lexer.next_spanned())
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    fn lex_error(input: &str) -> LexError {
+        Lexer::new(input).find_map(Result::err).unwrap()
+    }
+
+    /// Joins the tokens as they appear in the source, separated by spaces.
+    fn source_text(tokens: &[Token]) -> String {
+        tokens
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn test_lexer_skips_comments() {
+        // Given
+        let lines = r"// File name: projects/10/Square/SquareGame.jack
+
+        // (same as projects/9/Square/SquareGame.jack)
+        /**
+         * Implements the Square game.
+         * This simple game allows the user to move a black square around
+         * the screen, and change the square's size during the movement.
+         * When the game starts, a square of 30 by 30 pixels is shown at the
+         * top-left corner of the screen. The user controls the square as follows.
+         * The 4 arrow keys are used to move the square up, down, left, and right.
+         * The 'z' and 'x' keys are used, respectively, to decrement and increment
+         * the square's size. The 'q' key is used to quit the game.
+         */
+        class SquareGame {
+           field Square square; // the square of this game
+           field int direction; // the square's current direction: 
+                                // 0=none, 1=up, 2=down, 3=left, 4=right"
+            .to_string();
+
+        // When
+        let source = source_text(&tokens(&lines));
+
+        // Then
+        assert_eq!(
+            source,
+            "class SquareGame { field Square square ; field int direction ;"
+        );
+    }
+
+    #[test]
+    fn test_lexer_keeps_comment_markers_in_strings() {
+        for text in [
+            "http://example.com",
+            "/* not a comment */",
+            "/** not a doc comment */",
+            "a // b /* c",
+        ] {
+            // Given
+            let input = format!("let s = \"{}\"; // comment\n/* comment */ do f();", text);
+
+            // When
+            let tokens = tokens(&input);
+
+            // Then
+            pretty_assertions::assert_eq!(
+                tokens,
+                vec![
+                    Token::Keyword(Keyword::Let),
+                    Token::Identifier("s".into()),
+                    Token::Symbol(Symbol::Equal),
+                    Token::StringConst(text.into()),
+                    Token::Symbol(Symbol::Semicolon),
+                    Token::Keyword(Keyword::Do),
+                    Token::Identifier("f".into()),
+                    Token::Symbol(Symbol::ParenthesisLeft),
+                    Token::Symbol(Symbol::ParenthesisRight),
+                    Token::Symbol(Symbol::Semicolon),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_lexer_quotes_in_comments() {
+        // Given
+        let input = "// it's \"quoted\nlet /* \" */ x /** \" */ = \"//\";".to_string();
+
+        // When
+        let source = source_text(&tokens(&input));
+
+        // Then
+        assert_eq!(source, "let x = \"//\" ;");
+    }
+
+    #[test]
+    fn test_lexer() {
+        // Given
+        let input = r"/**
+        * Implements the Square game.
+        * This simple game allows the user to move a black square around
+        * the screen, and change the square's size during the movement.
+        * When the game starts, a square of 30 by 30 pixels is shown at the
+        * top-left corner of the screen. The user controls the square as follows.
+        * The 4 arrow keys are used to move the square up, down, left, and right.
+        * The 'z' and 'x' keys are used, respectively, to decrement and increment
+        * the square's size. The 'q' key is used to quit the game.
+        */
+       class SquareGame {
+          field Square square; // the square of this game
+          field int direction; // the square's current direction: 
+                               // 0=none, 1=up, 2=down, 3=left, 4=right
+       
+          /** Constructs a new Square Game. */
+          constructor SquareGame new() {
+             // Creates a 30 by 30 pixels square and positions it at the top-left
+             // of the screen.
+             let square = Square.new(0, 0, 30);
+             let direction = 0;  // initial state is no movement
+             return this;
+          }"
+        .to_string();
+
+        // When
+        let tokens = tokens(&input);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            tokens,
+            vec![
+                Token::Keyword(Keyword::Class),
+                Token::Identifier("SquareGame".into()),
+                Token::Symbol(Symbol::CurlLeft),
+                Token::Keyword(Keyword::Field),
+                Token::Identifier("Square".into()),
+                Token::Identifier("square".into()),
+                Token::Symbol(Symbol::Semicolon),
+                Token::Keyword(Keyword::Field),
+                Token::Keyword(Keyword::Int),
+                Token::Identifier("direction".into()),
+                Token::Symbol(Symbol::Semicolon),
+                Token::Keyword(Keyword::Constructor),
+                Token::Identifier("SquareGame".into()),
+                Token::Identifier("new".into()),
+                Token::Symbol(Symbol::ParenthesisLeft),
+                Token::Symbol(Symbol::ParenthesisRight),
+                Token::Symbol(Symbol::CurlLeft),
+                Token::Keyword(Keyword::Let),
+                Token::Identifier("square".into()),
+                Token::Symbol(Symbol::Equal),
+                Token::Identifier("Square".into()),
+                Token::Symbol(Symbol::Dot),
+                Token::Identifier("new".into()),
+                Token::Symbol(Symbol::ParenthesisLeft),
+                Token::IntConst(0),
+                Token::Symbol(Symbol::Comma),
+                Token::IntConst(0),
+                Token::Symbol(Symbol::Comma),
+                Token::IntConst(30),
+                Token::Symbol(Symbol::ParenthesisRight),
+                Token::Symbol(Symbol::Semicolon),
+                Token::Keyword(Keyword::Let),
+                Token::Identifier("direction".into()),
+                Token::Symbol(Symbol::Equal),
+                Token::IntConst(0),
+                Token::Symbol(Symbol::Semicolon),
+                Token::Keyword(Keyword::Return),
+                Token::Keyword(Keyword::This),
+                Token::Symbol(Symbol::Semicolon),
+                Token::Symbol(Symbol::CurlRight),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_complex() {
+        // Given
+        let input = r#"
+            let length = Keyboard.readInt("HOW MANY NUMBERS? ");"#
+            .to_string();
+
+        // When
+        let tokens = tokens(&input);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            tokens,
+            vec![
+                Token::Keyword(Keyword::Let),
+                Token::Identifier("length".into()),
+                Token::Symbol(Symbol::Equal),
+                Token::Identifier("Keyboard".into()),
+                Token::Symbol(Symbol::Dot),
+                Token::Identifier("readInt".into()),
+                Token::Symbol(Symbol::ParenthesisLeft),
+                Token::StringConst("HOW MANY NUMBERS? ".into()),
+                Token::Symbol(Symbol::ParenthesisRight),
+                Token::Symbol(Symbol::Semicolon),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_integer_constants() {
+        // Given
+        let input = "let x = 0 + 007 + 32767-1;".to_string();
+
+        // When
+        let tokens = tokens(&input);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            tokens,
+            vec![
+                Token::Keyword(Keyword::Let),
+                Token::Identifier("x".into()),
+                Token::Symbol(Symbol::Equal),
+                Token::IntConst(0),
+                Token::Symbol(Symbol::Plus),
+                Token::IntConst(7),
+                Token::Symbol(Symbol::Plus),
+                Token::IntConst(32767),
+                Token::Symbol(Symbol::Minus),
+                Token::IntConst(1),
+                Token::Symbol(Symbol::Semicolon),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_integer_out_of_range() {
+        for (input, value, start) in [
+            ("let x = 32768;", "32768", 8),
+            ("let x = 40000;", "40000", 8),
+            ("do f(70000);", "70000", 5),
+            ("let x = 123456789012345678901;", "123456789012345678901", 8),
+        ] {
+            // When
+            let error = lex_error(input);
+
+            // Then
+            assert_eq!(
+                error,
+                LexError::IntegerOutOfRange {
+                    value: value.to_string(),
+                    span: Span {
+                        file: None,
+                        start,
+                        end: start + value.len(),
+                        line: 1,
+                        column: start + 1,
+                    },
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_lexer_malformed_integer() {
+        // When
+        let error = lex_error("let x = 12ab;");
+
+        // Then
+        assert_eq!(
+            error,
+            LexError::MalformedIntegerConstant {
+                value: String::from("12ab"),
+                span: Span {
+                    file: None,
+                    start: 8,
+                    end: 12,
+                    line: 1,
+                    column: 9,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_lexer_identifier_with_digits() {
+        // When
+        let tokens = tokens("x1 _2");
+
+        // Then
+        assert_eq!(tokens[0], Token::Identifier("x1".into()));
+        assert_eq!(tokens[1], Token::Identifier("_2".into()));
+    }
+
+    #[test]
+    fn test_lexer_spans() {
+        // Given
+        let input =
+            "/* header */\nclass Main {\n\t// comment\n\tfield String s; /* é */ \"a b\"\n}"
+                .to_string();
+
+        // When
+        let tokens = spanned_tokens(&input);
+
+        // Then
+        let positions: Vec<_> = tokens
+            .iter()
+            .map(|t| (&input[t.span.start..t.span.end], t.span.line, t.span.column))
+            .collect();
+        pretty_assertions::assert_eq!(
+            positions,
+            vec![
+                ("class", 2, 1),
+                ("Main", 2, 7),
+                ("{", 2, 12),
+                ("field", 4, 2),
+                ("String", 4, 8),
+                ("s", 4, 15),
+                (";", 4, 16),
+                ("\"a b\"", 4, 26),
+                ("}", 5, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_borrows_source() {
+        // Given
+        let input = r#"let s = "abc";"#;
+
+        // When
+        let tokens = tokens(input);
+
+        // Then
+        assert!(matches!(&tokens[1], Token::Identifier(Cow::Borrowed("s"))));
+        assert!(matches!(
+            &tokens[3],
+            Token::StringConst(Cow::Borrowed("abc"))
+        ));
+    }
+
+    #[test]
+    fn test_lexer_stops_after_error() {
+        // Given
+        let mut lexer = Lexer::new("let @ x");
+
+        // When
+        let first = lexer.next();
+        let second = lexer.next();
+        let third = lexer.next();
+
+        // Then
+        assert_eq!(first, Some(Ok(Token::Keyword(Keyword::Let))));
+        assert!(matches!(
+            second,
+            Some(Err(LexError::IllegalCharacter { character: '@', .. }))
+        ));
+        assert_eq!(third, None);
+    }
+
+    #[test]
+    fn test_lexer_end_span() {
+        // Given
+        let mut lexer = Lexer::new("class\n  // comment\n  ");
+
+        // When
+        let tokens: Vec<_> = lexer.by_ref().collect();
+
+        // Then
+        assert_eq!(tokens, vec![Ok(Token::Keyword(Keyword::Class))]);
+        let span = lexer.end_span();
+        assert_eq!((span.start, span.line, span.column), (21, 3, 3));
+    }
+
+    #[test]
+    fn test_leading_trivia() {
+        // Given
+        let input = "/** doc */ /**/ /* block */\n// line\nclass";
+        let mut lexer = Lexer::new(input);
+
+        // When
+        let trivia = lexer.leading_trivia().unwrap();
+        let token = lexer.next();
+        let trailing = lexer.leading_trivia().unwrap();
+
+        // Then
+        let kinds: Vec<_> = trivia.iter().map(|t| (t.kind, t.text.as_ref())).collect();
+        pretty_assertions::assert_eq!(
+            kinds,
+            vec![
+                (TriviaKind::DocComment, "/** doc */"),
+                (TriviaKind::Whitespace, " "),
+                (TriviaKind::BlockComment, "/**/"),
+                (TriviaKind::Whitespace, " "),
+                (TriviaKind::BlockComment, "/* block */"),
+                (TriviaKind::Whitespace, "\n"),
+                (TriviaKind::LineComment, "// line"),
+                (TriviaKind::Whitespace, "\n"),
+            ]
+        );
+        assert_eq!((trivia[6].span.line, trivia[6].span.column), (2, 1));
+        assert_eq!(token, Some(Ok(Token::Keyword(Keyword::Class))));
+        assert!(trailing.is_empty());
+    }
+}
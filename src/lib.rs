@@ -1,3 +1,23 @@
+pub mod ast;
+pub mod checker;
+pub mod code_generator;
+pub mod compilation_engine;
+pub mod control_flow;
+pub mod cst;
+pub mod diagnostics;
+pub mod dot;
+pub mod errors;
+pub mod formatter;
+pub mod jobs;
+pub mod json;
+pub mod lexer;
+pub mod os;
+pub mod parser;
+pub mod project;
+pub mod symbol_table;
+#[cfg(test)]
+mod test_utils;
 pub mod tokenizer;
 pub mod tokens;
-pub mod utils;
+pub mod type_checker;
+pub mod vm_writer;
@@ -1,55 +1,557 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    io::IsTerminal,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+};
 
-use clap::{command, Parser};
-use compiler::tokenizer::JackTokenizer;
-use walkdir::WalkDir;
+use clap::{error::ErrorKind, Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use compiler::{
+    ast::Class,
+    checker::{self, Program},
+    code_generator::compile_class,
+    compilation_engine::CompilationEngine,
+    control_flow,
+    diagnostics::{Diagnostic, Renderer, Severity},
+    dot, formatter, jobs, parser,
+    project::{self, CompilationUnit},
+    tokenizer::JackTokenizer,
+    type_checker,
+};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
-struct Args {
-    /// Optional path to a file or a directory
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write the tokens of each .jack file
+    Tokenize {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        output: Output,
+        /// Format of the tokens
+        #[arg(short, long, value_enum, default_value_t)]
+        format: TokensFormat,
+    },
+    /// Write the parse tree of each .jack file
+    Parse {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        output: Output,
+        /// Format of the parse tree
+        #[arg(short, long, value_enum, default_value_t)]
+        format: TreeFormat,
+        /// Write the category, index and usage of each identifier in the parse tree
+        #[arg(short, long)]
+        extended: bool,
+        /// Draw the chains of nonterminals having a single child as one vertex,
+        /// with `--format dot`
+        #[arg(long)]
+        collapse: bool,
+        /// Draw the abstract syntax tree instead of the parse tree, with
+        /// `--format dot`
+        #[arg(long, conflicts_with_all = ["extended", "collapse"])]
+        ast: bool,
+    },
+    /// Compile each .jack file to VM code
+    Compile {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        output: Output,
+        #[command(flatten)]
+        checks: Checks,
+    },
+    /// Report the errors of each .jack file without writing any output
+    Check {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        checks: Checks,
+    },
+    /// Rewrite the .jack files in the canonical layout
+    Fmt {
+        #[command(flatten)]
+        input: Input,
+        /// Only list the files that aren't formatted, failing if there are any
+        #[arg(long)]
+        check: bool,
+        /// Write the formatted sources to stdout instead of rewriting the files
+        #[arg(long, conflicts_with = "check")]
+        stdout: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+struct Input {
+    /// Files or directories to process, the current directory by default.
+    /// Directories are searched recursively and each directory holding
+    /// .jack files is a separate program
+    paths: Vec<PathBuf>,
+    /// A file or a directory to process, in addition to the paths
+    #[arg(short, long)]
+    path: Vec<PathBuf>,
+    /// Number of files processed in parallel, one per CPU by default
+    #[arg(short, long)]
+    jobs: Option<NonZeroUsize>,
+    /// When to color the diagnostics
+    #[arg(long, value_enum, default_value_t)]
+    color: ColorChoice,
+}
+
+impl Input {
+    fn paths(&self) -> Vec<PathBuf> {
+        let paths: Vec<_> = self.path.iter().chain(&self.paths).cloned().collect();
+        match paths.is_empty() {
+            true => vec![PathBuf::from(".")],
+            false => paths,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+struct Output {
+    /// Directory to write the output files to, instead of next to the
+    /// sources, mirroring the directories of the sources below the path, or
+    /// below the common ancestor of the paths when there are several
     #[arg(short, long)]
-    path: Option<PathBuf>,
+    out_dir: Option<PathBuf>,
+    /// Write the output to stdout instead of files
+    #[arg(long, conflicts_with = "out_dir")]
+    stdout: bool,
+}
+
+#[derive(Args, Debug)]
+struct Checks {
+    /// Report the warnings (type errors and unreachable statements) as errors
+    #[arg(long)]
+    strict: bool,
+    /// A .jack file or directory declaring classes that the programs call
+    /// without compiling them, e.g. an OS other than the standard one. The
+    /// stubs override the OS declarations and only their signatures are used
+    #[arg(long)]
+    stubs: Vec<PathBuf>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default)]
+enum ColorChoice {
+    /// Color when writing to a terminal, unless `NO_COLOR` is set
+    #[default]
+    Auto,
+    Always,
+    /// Plain text, e.g. for logs
+    Never,
+}
+
+impl ColorChoice {
+    fn enabled(self) -> bool {
+        match self {
+            Self::Auto => std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
+/// Format of the tokens.
+#[derive(ValueEnum, Debug, Clone, Copy, Default)]
+enum TokensFormat {
+    /// The XML of the nand2tetris course
+    #[default]
+    Xml,
+    /// JSON with the spans of the tokens
+    Json,
+}
+
+impl TokensFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Xml => "xml",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Format of the parse trees.
+#[derive(ValueEnum, Debug, Clone, Copy, Default)]
+enum TreeFormat {
+    /// The XML of the nand2tetris course
+    #[default]
+    Xml,
+    /// JSON with the spans of the tokens
+    Json,
+    /// A Graphviz graph
+    Dot,
+}
+
+impl TreeFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Xml => "xml",
+            Self::Json => "json",
+            Self::Dot => "dot",
+        }
+    }
+}
+
+/// The reasons a file failed.
+type Diagnostics = Vec<Diagnostic>;
+
+/// The content written for a file and the suffix of its file name
+/// (e.g. `TCompiler.xml` for `MainTCompiler.xml`).
+struct FileOutput {
+    content: String,
+    suffix: String,
 }
 
 fn main() {
-    let path = Args::parse().path.unwrap_or_else(|| PathBuf::from("."));
-    let jack_files = WalkDir::new(path)
-        .max_depth(1)
-        .into_iter()
-        .filter_map(Result::ok)
-        .filter(|entry| {
-            entry.file_type().is_file()
-                && entry
-                    .path()
-                    .extension()
-                    .map(|ext| ext == "jack")
-                    .unwrap_or_default()
+    let cli = Cli::parse();
+    if let Command::Parse {
+        format, ast: true, ..
+    } = &cli.command
+    {
+        if !matches!(format, TreeFormat::Dot) {
+            Cli::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "the abstract syntax tree is only written with `--format dot`",
+                )
+                .exit();
+        }
+    }
+    let (input, output) = match &cli.command {
+        Command::Tokenize { input, output, .. }
+        | Command::Parse { input, output, .. }
+        | Command::Compile { input, output, .. } => (input, Some(output)),
+        Command::Check { input, .. } | Command::Fmt { input, .. } => (input, None),
+    };
+
+    let units = project::discover(&input.paths()).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    let file_count: usize = units.iter().map(|unit| unit.files.len()).sum();
+    if file_count == 0 {
+        eprintln!("no .jack file found");
+        std::process::exit(1);
+    }
+
+    // Each file along with the index of its unit
+    let files: Vec<_> = units
+        .iter()
+        .enumerate()
+        .flat_map(|(u, unit)| unit.files.iter().map(move |j| (u, unit, j)))
+        .collect();
+    if let Some(output) = output.filter(|output| !output.stdout) {
+        check_outputs(&files, output);
+    }
+    let jobs = input.jobs.unwrap_or_else(jobs::default_jobs);
+    let renderer = Renderer::new(input.color.enabled());
+
+    // The commands checking the calls between the classes of a unit parse
+    // all its files first
+    let classes = match &cli.command {
+        Command::Compile { .. } | Command::Check { .. } => {
+            jobs::map(&files, jobs, |(_, _, j)| parse_file(j))
+        }
+        _ => Vec::new(),
+    };
+    // The other files of the directories of the files given directly, read
+    // only for the classes they declare
+    let contexts: Vec<Vec<Class>> = match &cli.command {
+        Command::Compile { .. } | Command::Check { .. } => units
+            .iter()
+            .map(|unit| {
+                let classes = jobs::map(&unit.context, jobs, |j| parse_file(j));
+                classes.into_iter().filter_map(Result::ok).collect()
+            })
+            .collect(),
+        _ => vec![Vec::new(); units.len()],
+    };
+    let stubs = match &cli.command {
+        Command::Compile { checks, .. } | Command::Check { checks, .. } => {
+            parse_stubs(&checks.stubs, &renderer)
+        }
+        _ => Vec::new(),
+    };
+    let programs: Vec<_> = (0..units.len())
+        .map(|u| {
+            let classes = files.iter().zip(&classes);
+            Program::new(
+                classes
+                    .filter(|((file_unit, ..), _)| *file_unit == u)
+                    .filter_map(|(_, class)| class.as_ref().ok())
+                    .chain(&contexts[u])
+                    .chain(&stubs),
+            )
         })
-        .map(|entry| entry.path().to_path_buf())
-        .collect::<Vec<_>>();
-
-    for j in jack_files {
-        let name = j
-            .file_stem()
-            .unwrap_or_default()
-            .to_str()
-            .unwrap_or_default()
-            .to_string()
-            + "Compiler.xml";
-        let mut output_path = j.clone();
-        output_path.set_file_name(name);
-
-        let mut tokenizer = JackTokenizer::new(j);
-        let mut acc = String::new();
-        acc += "<tokens>\n";
-        while tokenizer.has_more_tokens() {
-            let token = tokenizer.current_token();
-            acc += &(token.start_xml() + " " + &token.to_xml() + " " + &token.end_xml() + "\n");
-            tokenizer.advance();
-        }
-        acc += r"</tokens>";
-
-        std::fs::write(output_path, acc).expect("failed to write output");
+        .collect();
+
+    let indices: Vec<_> = (0..files.len()).collect();
+    let results = jobs::map(&indices, jobs, |&i| {
+        let (u, unit, j) = files[i];
+        let parsed = classes.get(i).map(|class| (class, &programs[u]));
+        let mut warnings = Vec::new();
+        let result = run(&cli.command, j, parsed, &mut warnings)
+            .and_then(|file_output| match (file_output, output) {
+                (Some(file_output), Some(output)) => write_output(unit, j, file_output, output),
+                (Some(file_output), None) => Ok(Some(file_output.content)),
+                (None, _) => Ok(None),
+            })
+            .map_err(|diagnostics| render(&renderer, j, &diagnostics));
+        (render(&renderer, j, &warnings), result)
+    });
+
+    // The results are reported in the order of the files, whatever the
+    // order they were computed in
+    let mut failed = 0;
+    for (warnings, result) in results {
+        eprint!("{}", warnings);
+        match result {
+            Ok(Some(stdout)) => print!("{}", stdout),
+            Ok(None) => {}
+            Err(diagnostics) => {
+                eprint!("{}", diagnostics);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        eprintln!("{} of {} files failed", failed, file_count);
+        std::process::exit(1);
+    }
+}
+
+/// The class parsed from a file along with the program of its unit, for the
+/// commands checking the calls between classes.
+type Parsed<'a> = Option<(&'a Result<Class, Diagnostics>, &'a Program)>;
+
+/// Runs the command on a file and returns the output to write, if any.
+/// The warnings about the file are added to `warnings`.
+fn run(
+    command: &Command,
+    j: &Path,
+    parsed: Parsed,
+    warnings: &mut Diagnostics,
+) -> Result<Option<FileOutput>, Diagnostics> {
+    let output = match command {
+        Command::Tokenize { format, .. } => {
+            let tokenizer = JackTokenizer::new(j.to_path_buf()).map_err(report)?;
+            Some(FileOutput {
+                content: match format {
+                    TokensFormat::Xml => tokenizer.to_xml(),
+                    TokensFormat::Json => tokenizer.to_json(),
+                },
+                suffix: format!("TCompiler.{}", format.extension()),
+            })
+        }
+        Command::Parse {
+            format: TreeFormat::Dot,
+            ast: true,
+            ..
+        } => Some(FileOutput {
+            content: dot::ast_to_dot(&parse_file(j)?),
+            suffix: String::from("Ast.dot"),
+        }),
+        Command::Parse {
+            format,
+            extended,
+            collapse,
+            ..
+        } => {
+            let tokenizer = JackTokenizer::new(j.to_path_buf()).map_err(report)?;
+            let engine = match extended {
+                true => CompilationEngine::extended(tokenizer),
+                false => CompilationEngine::new(tokenizer),
+            };
+            let cst = engine.compile_tree().map_err(report)?;
+            Some(FileOutput {
+                content: match format {
+                    TreeFormat::Xml => cst.to_xml(),
+                    TreeFormat::Json => cst.to_json(),
+                    TreeFormat::Dot => cst.to_dot(*collapse),
+                },
+                suffix: format!("Compiler.{}", format.extension()),
+            })
+        }
+        Command::Compile { checks, .. } => Some(FileOutput {
+            content: compile_class(check(parsed, checks.strict, warnings)?)
+                .map_err(|errors| errors.iter().map(Diagnostic::from).collect::<Vec<_>>())?,
+            suffix: String::from(".vm"),
+        }),
+        Command::Check { checks, .. } => {
+            check(parsed, checks.strict, warnings)?;
+            None
+        }
+        Command::Fmt { check, stdout, .. } => fmt(j, *check, *stdout)?.map(|content| FileOutput {
+            content,
+            suffix: String::from(".jack"),
+        }),
+    };
+    Ok(output)
+}
+
+/// Parses the class of the file.
+fn parse_file(j: &Path) -> Result<Class, Diagnostics> {
+    parse(JackTokenizer::new(j.to_path_buf()).map_err(report)?)
+}
+
+/// Parses the classes of the stubs, exiting if any of them fails.
+fn parse_stubs(paths: &[PathBuf], renderer: &Renderer) -> Vec<Class> {
+    let units = project::discover(paths).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    units
+        .iter()
+        .flat_map(|unit| &unit.files)
+        .map(|j| {
+            parse_file(j).unwrap_or_else(|diagnostics| {
+                eprint!("{}", render(renderer, j, &diagnostics));
+                std::process::exit(1);
+            })
+        })
+        .collect()
+}
+
+/// Returns the parsed class once checked against the program of its unit.
+/// The warnings (e.g. type errors) are added to `warnings`, unless `strict`.
+fn check<'a>(
+    parsed: Parsed<'a>,
+    strict: bool,
+    warnings: &mut Diagnostics,
+) -> Result<&'a Class, Diagnostics> {
+    let (class, program) = parsed.expect("the file is parsed along with its unit");
+    let class = class.as_ref().map_err(Clone::clone)?;
+    let semantic_errors = checker::check_class(class, program);
+    let control_flow_errors = control_flow::check_control_flow(class);
+    let (mut errors, mut diagnostics): (Vec<_>, Vec<_>) = semantic_errors
+        .iter()
+        .map(Diagnostic::from)
+        .chain(control_flow_errors.iter().map(Diagnostic::from))
+        .partition(|d| d.severity == Severity::Error);
+    if !errors.is_empty() {
+        errors.sort_by_key(primary_start);
+        warnings.extend(diagnostics);
+        return Err(errors);
+    }
+
+    let type_errors = type_checker::check_types(class, program);
+    diagnostics.extend(type_errors.iter().map(Diagnostic::from));
+    diagnostics.sort_by_key(primary_start);
+    match strict {
+        true if !diagnostics.is_empty() => Err(diagnostics
+            .into_iter()
+            .map(|d| d.with_severity(Severity::Error))
+            .collect()),
+        true => Ok(class),
+        false => {
+            warnings.extend(diagnostics);
+            Ok(class)
+        }
+    }
+}
+
+/// Returns the start of the primary label of the diagnostic, to report the
+/// diagnostics of a file in the order of the source.
+fn primary_start(diagnostic: &Diagnostic) -> Option<usize> {
+    diagnostic.primary.as_ref().map(|label| label.span.start)
+}
+
+/// Parses the class of the tokens, reporting all its syntax errors.
+fn parse(tokenizer: JackTokenizer) -> Result<Class, Diagnostics> {
+    match parser::Parser::new(tokenizer).parse_recovering() {
+        (Some(class), errors) if errors.is_empty() => Ok(class),
+        (_, errors) => Err(errors.iter().map(Diagnostic::from).collect()),
+    }
+}
+
+/// Formats the file in place, or only checks it with `check`. Returns the
+/// formatted source instead with `stdout`.
+fn fmt(j: &Path, check: bool, stdout: bool) -> Result<Option<String>, Diagnostics> {
+    let source = std::fs::read_to_string(j).map_err(|e| io_error("read", j, e))?;
+    let formatted = formatter::format_source(source.clone()).map_err(report)?;
+    if stdout {
+        return Ok(Some(formatted));
+    } else if formatted != source {
+        match check {
+            true => return Err(vec![Diagnostic::error("the file isn't formatted")]),
+            false => std::fs::write(j, formatted).map_err(|e| io_error("write", j, e))?,
+        }
+    }
+    Ok(None)
+}
+
+/// Writes the output of a file of the unit to the output directory of the
+/// unit, or returns it to print with `stdout`.
+fn write_output(
+    unit: &CompilationUnit,
+    j: &Path,
+    file_output: FileOutput,
+    output: &Output,
+) -> Result<Option<String>, Diagnostics> {
+    if output.stdout {
+        return Ok(Some(file_output.content));
     }
+
+    let stem = j.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = format!("{}{}", stem, file_output.suffix);
+    let dir = unit.output_dir(output.out_dir.as_deref());
+    std::fs::create_dir_all(&dir).map_err(|e| io_error("create", &dir, e))?;
+    let path = dir.join(file_name);
+    std::fs::write(&path, file_output.content).map_err(|e| io_error("write", &path, e))?;
+    Ok(None)
+}
+
+/// Exits if two files would be written to the same path, e.g. the same
+/// directory given twice through different paths, since the output of one
+/// would overwrite the other.
+fn check_outputs(files: &[(usize, &CompilationUnit, &PathBuf)], output: &Output) {
+    let mut written: HashMap<(PathBuf, &OsStr), &Path> = HashMap::new();
+    for (_, unit, j) in files {
+        let dir = unit.output_dir(output.out_dir.as_deref());
+        let stem = j.file_stem().unwrap_or_default();
+        if let Some(other) = written.insert((dir.clone(), stem), j) {
+            eprintln!(
+                "the outputs of {} and {} would both be written to {}",
+                other.display(),
+                j.display(),
+                dir.display()
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+fn report<E>(error: E) -> Diagnostics
+where
+    for<'a> Diagnostic: From<&'a E>,
+{
+    vec![Diagnostic::from(&error)]
+}
+
+fn io_error(operation: &str, path: &Path, error: std::io::Error) -> Diagnostics {
+    vec![Diagnostic::error(format!(
+        "failed to {} {}: {}",
+        operation,
+        path.display(),
+        error
+    ))]
+}
+
+/// Renders the diagnostics of the file, each followed by a blank line.
+fn render(renderer: &Renderer, j: &Path, diagnostics: &[Diagnostic]) -> String {
+    let source = std::fs::read_to_string(j).unwrap_or_default();
+    diagnostics
+        .iter()
+        .map(|diagnostic| renderer.render(diagnostic, j, &source) + "\n")
+        .collect()
 }
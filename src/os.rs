@@ -0,0 +1,109 @@
+use std::sync::OnceLock;
+
+use crate::{ast::Class, parser::parse_class};
+
+const MATH: &str = "class Math {
+    function void init() {}
+    function int abs(int x) {}
+    function int multiply(int x, int y) {}
+    function int divide(int x, int y) {}
+    function int min(int x, int y) {}
+    function int max(int x, int y) {}
+    function int sqrt(int x) {}
+}";
+
+const STRING: &str = "class String {
+    constructor String new(int maxLength) {}
+    method void dispose() {}
+    method int length() {}
+    method char charAt(int j) {}
+    method void setCharAt(int j, char c) {}
+    method String appendChar(char c) {}
+    method void eraseLastChar() {}
+    method int intValue() {}
+    method void setInt(int val) {}
+    function char backSpace() {}
+    function char doubleQuote() {}
+    function char newLine() {}
+}";
+
+const ARRAY: &str = "class Array {
+    function Array new(int size) {}
+    method void dispose() {}
+}";
+
+const OUTPUT: &str = "class Output {
+    function void init() {}
+    function void moveCursor(int i, int j) {}
+    function void printChar(char c) {}
+    function void printString(String s) {}
+    function void printInt(int i) {}
+    function void println() {}
+    function void backSpace() {}
+}";
+
+const SCREEN: &str = "class Screen {
+    function void init() {}
+    function void clearScreen() {}
+    function void setColor(boolean b) {}
+    function void drawPixel(int x, int y) {}
+    function void drawLine(int x1, int y1, int x2, int y2) {}
+    function void drawRectangle(int x1, int y1, int x2, int y2) {}
+    function void drawCircle(int x, int y, int r) {}
+}";
+
+const KEYBOARD: &str = "class Keyboard {
+    function void init() {}
+    function char keyPressed() {}
+    function char readChar() {}
+    function String readLine(String message) {}
+    function int readInt(String message) {}
+}";
+
+const MEMORY: &str = "class Memory {
+    function void init() {}
+    function int peek(int address) {}
+    function void poke(int address, int value) {}
+    function Array alloc(int size) {}
+    function void deAlloc(Array o) {}
+}";
+
+const SYS: &str = "class Sys {
+    function void init() {}
+    function void halt() {}
+    function void error(int errorCode) {}
+    function void wait(int duration) {}
+}";
+
+/// Returns the declarations of the OS classes, parsed once, so that the calls
+/// to the OS can be checked without its source. They follow the OS API of the
+/// nand2tetris course, with empty subroutine bodies.
+pub fn classes() -> &'static [Class] {
+    static CLASSES: OnceLock<Vec<Class>> = OnceLock::new();
+    CLASSES.get_or_init(|| {
+        [MATH, STRING, ARRAY, OUTPUT, SCREEN, KEYBOARD, MEMORY, SYS]
+            .iter()
+            .map(|source| parse_class(source).expect("invalid OS declarations"))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classes() {
+        // When
+        let classes = classes();
+
+        // Then
+        let names: Vec<_> = classes.iter().map(|c| c.name.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["Math", "String", "Array", "Output", "Screen", "Keyboard", "Memory", "Sys"]
+        );
+        let subroutines: usize = classes.iter().map(|c| c.subroutines.len()).sum();
+        assert_eq!(subroutines, 49);
+    }
+}
@@ -0,0 +1,996 @@
+use crate::{
+    ast::{
+        BinaryOp, Class, ClassVarDec, ClassVarKind, Expression, Identifier, KeywordConst,
+        Parameter, Statement, SubroutineBody, SubroutineCall, SubroutineDec, SubroutineKind, Term,
+        Type, UnaryOp, VarDec,
+    },
+    errors::ParseError,
+    tokenizer::JackTokenizer,
+    tokens::{Keyword, Symbol, Token},
+};
+
+/// Parses the source code of a single Jack class into its AST.
+pub fn parse_class(source: &str) -> Result<Class, ParseError> {
+    Parser::new(JackTokenizer::from_source(source.to_string())?).parse()
+}
+
+/// The keywords starting a statement, where parsing resumes after a syntax
+/// error in a statement.
+const STATEMENT_KEYWORDS: [Keyword; 5] = [
+    Keyword::Let,
+    Keyword::Do,
+    Keyword::If,
+    Keyword::While,
+    Keyword::Return,
+];
+
+/// The keywords starting a class member. They can't appear in a subroutine
+/// body, so parsing resumes there after any syntax error.
+const MEMBER_KEYWORDS: [Keyword; 5] = [
+    Keyword::Static,
+    Keyword::Field,
+    Keyword::Constructor,
+    Keyword::Function,
+    Keyword::Method,
+];
+
+/// Recursive descent parser building the AST of a Jack class.
+///
+/// The parser recovers from syntax errors: a statement that can't be parsed
+/// becomes a `Statement::Error` and a class member that can't be parsed is
+/// left out, then parsing resumes at the next statement or member.
+#[derive(Debug)]
+pub struct Parser {
+    /// The tokenizer providing the input tokens
+    tokenizer: JackTokenizer,
+    /// The syntax errors recovered from so far
+    errors: Vec<ParseError>,
+}
+
+impl Parser {
+    pub fn new(tokenizer: JackTokenizer) -> Self {
+        Self {
+            tokenizer,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Parses the class and checks that no token follows it, returning the
+    /// first syntax error if there are any.
+    pub fn parse(self) -> Result<Class, ParseError> {
+        match self.parse_recovering() {
+            (Some(class), errors) if errors.is_empty() => Ok(class),
+            (_, mut errors) => Err(errors.remove(0)),
+        }
+    }
+
+    /// Parses the class and checks that no token follows it, returning all
+    /// the syntax errors in the order of the source. The class is partial if
+    /// there are errors, and `None` if its header couldn't be parsed.
+    pub fn parse_recovering(mut self) -> (Option<Class>, Vec<ParseError>) {
+        let class = match self.parse_class() {
+            Ok(class) => Some(class),
+            Err(error) => {
+                self.errors.push(error);
+                None
+            }
+        };
+        if let (Some(_), Some(token)) = (&class, self.tokenizer.peek_spanned(0)) {
+            let error = ParseError::unexpected("end of input", token);
+            self.errors.push(error);
+        }
+        (class, self.errors)
+    }
+
+    /// class: 'class' className '{' classVarDec* subroutineDec* '}'
+    fn parse_class(&mut self) -> Result<Class, ParseError> {
+        self.tokenizer.expect_keyword(&[Keyword::Class])?;
+        let name = self.identifier()?;
+        self.tokenizer.expect(Token::Symbol(Symbol::CurlLeft))?;
+
+        let mut var_decs = Vec::new();
+        let mut subroutines = Vec::new();
+        // Whether a subroutine was declared, after which no class variable can be
+        let mut in_subroutines = false;
+        loop {
+            let result = match (self.class_var_kind(), self.subroutine_kind()) {
+                (Some(kind), _) if !in_subroutines => {
+                    self.tokenizer.advance();
+                    self.parse_class_var_dec(kind).map(|dec| var_decs.push(dec))
+                }
+                (_, Some(kind)) => {
+                    in_subroutines = true;
+                    self.tokenizer.advance();
+                    self.parse_subroutine(kind).map(|dec| subroutines.push(dec))
+                }
+                _ if self.is_symbol(Symbol::CurlRight) || self.tokenizer.peek(0).is_none() => break,
+                _ => {
+                    // Not a member, e.g. a class variable declared after a subroutine
+                    let error = self.tokenizer.expect(Token::Symbol(Symbol::CurlRight));
+                    self.tokenizer.advance();
+                    error.map(|_| ())
+                }
+            };
+            if let Err(error) = result {
+                self.errors.push(error);
+                self.synchronize(&[]);
+            }
+        }
+        self.tokenizer.expect(Token::Symbol(Symbol::CurlRight))?;
+
+        Ok(Class {
+            name,
+            var_decs,
+            subroutines,
+        })
+    }
+
+    /// type varName (',' varName)* ';'
+    fn parse_class_var_dec(&mut self, kind: ClassVarKind) -> Result<ClassVarDec, ParseError> {
+        let ty = self.parse_type()?;
+        let names = self.parse_var_names()?;
+        Ok(ClassVarDec { kind, ty, names })
+    }
+
+    /// ('void' | type) subroutineName '(' parameterList ')' subroutineBody
+    fn parse_subroutine(&mut self, kind: SubroutineKind) -> Result<SubroutineDec, ParseError> {
+        let return_type = match self.is_keyword(Keyword::Void) {
+            true => {
+                self.tokenizer.advance();
+                None
+            }
+            false => Some(self.parse_type()?),
+        };
+        let name = self.identifier()?;
+        self.tokenizer
+            .expect(Token::Symbol(Symbol::ParenthesisLeft))?;
+        let parameters = self.parse_parameter_list()?;
+        self.tokenizer
+            .expect(Token::Symbol(Symbol::ParenthesisRight))?;
+        let body = self.parse_subroutine_body()?;
+
+        Ok(SubroutineDec {
+            kind,
+            return_type,
+            name,
+            parameters,
+            body,
+        })
+    }
+
+    /// parameterList: ((type varName) (',' type varName)*)?
+    fn parse_parameter_list(&mut self) -> Result<Vec<Parameter>, ParseError> {
+        let mut parameters = Vec::new();
+        if self.is_symbol(Symbol::ParenthesisRight) {
+            return Ok(parameters);
+        }
+        loop {
+            let ty = self.parse_type()?;
+            let name = self.identifier()?;
+            parameters.push(Parameter { ty, name });
+            if !self.eat_symbol(Symbol::Comma) {
+                return Ok(parameters);
+            }
+        }
+    }
+
+    /// subroutineBody: '{' varDec* statements '}'
+    fn parse_subroutine_body(&mut self) -> Result<SubroutineBody, ParseError> {
+        self.tokenizer.expect(Token::Symbol(Symbol::CurlLeft))?;
+        let mut var_decs = Vec::new();
+        while self.is_keyword(Keyword::Var) {
+            self.tokenizer.advance();
+            match self.parse_var_dec() {
+                Ok(var_dec) => var_decs.push(var_dec),
+                Err(error) => {
+                    self.errors.push(error);
+                    self.synchronize(&[&[Keyword::Var], &STATEMENT_KEYWORDS[..]].concat());
+                }
+            }
+        }
+        let statements = self.parse_statements()?;
+        self.tokenizer.expect(Token::Symbol(Symbol::CurlRight))?;
+
+        Ok(SubroutineBody {
+            var_decs,
+            statements,
+        })
+    }
+
+    /// type varName (',' varName)* ';'
+    fn parse_var_dec(&mut self) -> Result<VarDec, ParseError> {
+        let ty = self.parse_type()?;
+        let names = self.parse_var_names()?;
+        Ok(VarDec { ty, names })
+    }
+
+    /// statements: statement*
+    ///
+    /// Reads statements until the `}` closing the block, replacing the ones
+    /// that can't be parsed with `Statement::Error`.
+    fn parse_statements(&mut self) -> Result<Vec<Statement>, ParseError> {
+        let mut statements = Vec::new();
+        loop {
+            let start = self.tokenizer.current_span();
+            let statement = match self.tokenizer.peek(0) {
+                None | Some(Token::Symbol(Symbol::CurlRight)) => return Ok(statements),
+                Some(Token::Keyword(k)) if MEMBER_KEYWORDS.contains(k) => return Ok(statements),
+                Some(Token::Keyword(Keyword::Let)) => self.parse_let(),
+                Some(Token::Keyword(Keyword::If)) => self.parse_if(),
+                Some(Token::Keyword(Keyword::While)) => self.parse_while(),
+                Some(Token::Keyword(Keyword::Do)) => self.parse_do(),
+                Some(Token::Keyword(Keyword::Return)) => self.parse_return(),
+                Some(_) => {
+                    let token = self.tokenizer.take("a statement")?;
+                    Err(ParseError::unexpected("a statement", &token))
+                }
+            };
+            match statement {
+                Ok(statement) => statements.push(statement),
+                Err(error) => {
+                    self.errors.push(error);
+                    self.synchronize(&STATEMENT_KEYWORDS);
+                    statements.push(Statement::Error(self.tokenizer.span_from(&start)));
+                }
+            }
+        }
+    }
+
+    /// 'let' varName ('[' expression ']')? '=' expression ';'
+    fn parse_let(&mut self) -> Result<Statement, ParseError> {
+        let start = self.tokenizer.current_span();
+        self.tokenizer.expect_keyword(&[Keyword::Let])?;
+        let name = self.identifier()?;
+        let index = match self.eat_symbol(Symbol::SquareBracketLeft) {
+            true => {
+                let index = self.parse_expression()?;
+                self.tokenizer
+                    .expect(Token::Symbol(Symbol::SquareBracketRight))?;
+                Some(Box::new(index))
+            }
+            false => None,
+        };
+        self.tokenizer.expect(Token::Symbol(Symbol::Equal))?;
+        let value = self.parse_expression()?;
+        self.tokenizer.expect(Token::Symbol(Symbol::Semicolon))?;
+
+        Ok(Statement::Let {
+            name,
+            index,
+            value,
+            span: self.tokenizer.span_from(&start),
+        })
+    }
+
+    /// 'if' '(' expression ')' '{' statements '}' ('else' '{' statements '}')?
+    fn parse_if(&mut self) -> Result<Statement, ParseError> {
+        let start = self.tokenizer.current_span();
+        self.tokenizer.expect_keyword(&[Keyword::If])?;
+        let condition = self.parse_condition()?;
+        let statements = self.parse_block()?;
+        let else_statements = match self.is_keyword(Keyword::Else) {
+            true => {
+                self.tokenizer.advance();
+                Some(self.parse_block()?)
+            }
+            false => None,
+        };
+
+        Ok(Statement::If {
+            condition,
+            statements,
+            else_statements,
+            span: self.tokenizer.span_from(&start),
+        })
+    }
+
+    /// 'while' '(' expression ')' '{' statements '}'
+    fn parse_while(&mut self) -> Result<Statement, ParseError> {
+        let start = self.tokenizer.current_span();
+        self.tokenizer.expect_keyword(&[Keyword::While])?;
+        let condition = self.parse_condition()?;
+        let statements = self.parse_block()?;
+
+        Ok(Statement::While {
+            condition,
+            statements,
+            span: self.tokenizer.span_from(&start),
+        })
+    }
+
+    /// 'do' subroutineCall ';'
+    fn parse_do(&mut self) -> Result<Statement, ParseError> {
+        let start = self.tokenizer.current_span();
+        self.tokenizer.expect_keyword(&[Keyword::Do])?;
+        let name = self.identifier()?;
+        let call = self.parse_subroutine_call(name)?;
+        self.tokenizer.expect(Token::Symbol(Symbol::Semicolon))?;
+
+        Ok(Statement::Do(call, self.tokenizer.span_from(&start)))
+    }
+
+    /// 'return' expression? ';'
+    fn parse_return(&mut self) -> Result<Statement, ParseError> {
+        let start = self.tokenizer.current_span();
+        self.tokenizer.expect_keyword(&[Keyword::Return])?;
+        let value = match self.is_symbol(Symbol::Semicolon) {
+            true => None,
+            false => Some(self.parse_expression()?),
+        };
+        self.tokenizer.expect(Token::Symbol(Symbol::Semicolon))?;
+
+        Ok(Statement::Return(value, self.tokenizer.span_from(&start)))
+    }
+
+    /// expression: term (op term)*
+    pub fn parse_expression(&mut self) -> Result<Expression, ParseError> {
+        let start = self.tokenizer.current_span();
+        let term = self.parse_term()?;
+        let mut ops = Vec::new();
+        while let Some(op) = self.binary_op() {
+            self.tokenizer.advance();
+            ops.push((op, self.parse_term()?));
+        }
+        let span = self.tokenizer.span_from(&start);
+        Ok(Expression { term, ops, span })
+    }
+
+    /// term: integerConstant | stringConstant | keywordConstant | varName |
+    /// varName '[' expression ']' | subroutineCall | '(' expression ')' | unaryOp term
+    fn parse_term(&mut self) -> Result<Term, ParseError> {
+        let checkpoint = self.tokenizer.checkpoint();
+        let token = self.tokenizer.take("a term")?;
+        // Leaves the token to resume parsing from after the error
+        let mut unexpected = |token| {
+            self.tokenizer.rewind(checkpoint);
+            Err(ParseError::unexpected("a term", token))
+        };
+        let term = match &token.token {
+            Token::IntConst(i) => Term::IntConst(*i, token.span.clone()),
+            Token::StringConst(s) => Term::StringConst(s.to_string(), token.span.clone()),
+            Token::Keyword(k) => match KeywordConst::from_keyword(k) {
+                Some(k) => Term::KeywordConst(k, token.span.clone()),
+                None => return unexpected(&token),
+            },
+            Token::Symbol(Symbol::ParenthesisLeft) => {
+                let expression = self.parse_expression()?;
+                self.tokenizer
+                    .expect(Token::Symbol(Symbol::ParenthesisRight))?;
+                Term::Parenthesized(Box::new(expression), self.tokenizer.span_from(&token.span))
+            }
+            Token::Symbol(s) => match UnaryOp::from_symbol(s) {
+                Some(op) => {
+                    let term = self.parse_term()?;
+                    Term::Unary(op, Box::new(term), self.tokenizer.span_from(&token.span))
+                }
+                None => return unexpected(&token),
+            },
+            Token::Identifier(name) => {
+                let name = Identifier {
+                    name: name.to_string(),
+                    span: token.span.clone(),
+                };
+                if self.eat_symbol(Symbol::SquareBracketLeft) {
+                    let index = self.parse_expression()?;
+                    self.tokenizer
+                        .expect(Token::Symbol(Symbol::SquareBracketRight))?;
+                    Term::ArrayAccess(name, Box::new(index), self.tokenizer.span_from(&token.span))
+                } else if self.is_symbol(Symbol::ParenthesisLeft) || self.is_symbol(Symbol::Dot) {
+                    let call = self.parse_subroutine_call(name)?;
+                    Term::Call(Box::new(call), self.tokenizer.span_from(&token.span))
+                } else {
+                    Term::VarName(name)
+                }
+            }
+        };
+        Ok(term)
+    }
+
+    /// The rest of a subroutineCall once its first identifier was consumed:
+    /// ('.' subroutineName)? '(' expressionList ')'
+    fn parse_subroutine_call(&mut self, first: Identifier) -> Result<SubroutineCall, ParseError> {
+        let (receiver, name) = match self.eat_symbol(Symbol::Dot) {
+            true => (Some(first), self.identifier()?),
+            false => (None, first),
+        };
+        self.tokenizer
+            .expect(Token::Symbol(Symbol::ParenthesisLeft))?;
+        let arguments = self.parse_expression_list()?;
+        self.tokenizer
+            .expect(Token::Symbol(Symbol::ParenthesisRight))?;
+
+        Ok(SubroutineCall {
+            receiver,
+            name,
+            arguments,
+        })
+    }
+
+    /// expressionList: (expression (',' expression)*)?
+    fn parse_expression_list(&mut self) -> Result<Vec<Expression>, ParseError> {
+        let mut expressions = Vec::new();
+        if self.is_symbol(Symbol::ParenthesisRight) {
+            return Ok(expressions);
+        }
+        loop {
+            expressions.push(self.parse_expression()?);
+            if !self.eat_symbol(Symbol::Comma) {
+                return Ok(expressions);
+            }
+        }
+    }
+
+    /// '(' expression ')'
+    fn parse_condition(&mut self) -> Result<Expression, ParseError> {
+        self.tokenizer
+            .expect(Token::Symbol(Symbol::ParenthesisLeft))?;
+        let condition = self.parse_expression()?;
+        self.tokenizer
+            .expect(Token::Symbol(Symbol::ParenthesisRight))?;
+        Ok(condition)
+    }
+
+    /// '{' statements '}'
+    fn parse_block(&mut self) -> Result<Vec<Statement>, ParseError> {
+        self.tokenizer.expect(Token::Symbol(Symbol::CurlLeft))?;
+        let statements = self.parse_statements()?;
+        self.tokenizer.expect(Token::Symbol(Symbol::CurlRight))?;
+        Ok(statements)
+    }
+
+    /// type: 'int' | 'char' | 'boolean' | className
+    fn parse_type(&mut self) -> Result<Type, ParseError> {
+        let checkpoint = self.tokenizer.checkpoint();
+        let token = self.tokenizer.take("a type")?;
+        match &token.token {
+            Token::Keyword(Keyword::Int) => Ok(Type::Int),
+            Token::Keyword(Keyword::Char) => Ok(Type::Char),
+            Token::Keyword(Keyword::Boolean) => Ok(Type::Boolean),
+            Token::Identifier(name) => Ok(Type::Class(name.to_string())),
+            _ => {
+                self.tokenizer.rewind(checkpoint);
+                Err(ParseError::unexpected("a type", &token))
+            }
+        }
+    }
+
+    /// varName (',' varName)* ';'
+    fn parse_var_names(&mut self) -> Result<Vec<Identifier>, ParseError> {
+        let mut names = vec![self.identifier()?];
+        while self.eat_symbol(Symbol::Comma) {
+            names.push(self.identifier()?);
+        }
+        self.tokenizer.expect(Token::Symbol(Symbol::Semicolon))?;
+        Ok(names)
+    }
+
+    /// Consumes the current token if it is an identifier and returns it.
+    fn identifier(&mut self) -> Result<Identifier, ParseError> {
+        let span = self.tokenizer.current_span();
+        let name = self.tokenizer.expect_identifier()?;
+        Ok(Identifier { name, span })
+    }
+
+    fn class_var_kind(&self) -> Option<ClassVarKind> {
+        match self.tokenizer.peek(0) {
+            Some(Token::Keyword(Keyword::Static)) => Some(ClassVarKind::Static),
+            Some(Token::Keyword(Keyword::Field)) => Some(ClassVarKind::Field),
+            _ => None,
+        }
+    }
+
+    fn subroutine_kind(&self) -> Option<SubroutineKind> {
+        match self.tokenizer.peek(0) {
+            Some(Token::Keyword(Keyword::Constructor)) => Some(SubroutineKind::Constructor),
+            Some(Token::Keyword(Keyword::Function)) => Some(SubroutineKind::Function),
+            Some(Token::Keyword(Keyword::Method)) => Some(SubroutineKind::Method),
+            _ => None,
+        }
+    }
+
+    fn binary_op(&self) -> Option<BinaryOp> {
+        match self.tokenizer.peek(0) {
+            Some(Token::Symbol(s)) => BinaryOp::from_symbol(s),
+            _ => None,
+        }
+    }
+
+    fn is_keyword(&self, keyword: Keyword) -> bool {
+        self.tokenizer.peek(0) == Some(&Token::Keyword(keyword))
+    }
+
+    fn is_symbol(&self, symbol: Symbol) -> bool {
+        self.tokenizer.peek(0) == Some(&Token::Symbol(symbol))
+    }
+
+    /// Skips the tokens following a syntax error up to where parsing can
+    /// resume: one of the `stops` keywords or the `}` closing the current
+    /// block, or right after a `;`. Stops at the keywords starting a class
+    /// member even in a nested block, whose `}` is then likely missing.
+    fn synchronize(&mut self, stops: &[Keyword]) {
+        let mut depth = 0;
+        while let Some(token) = self.tokenizer.peek(0) {
+            match token {
+                Token::Keyword(k) if MEMBER_KEYWORDS.contains(k) => return,
+                Token::Keyword(k) if depth == 0 && stops.contains(k) => return,
+                Token::Symbol(Symbol::CurlRight) if depth == 0 => return,
+                Token::Symbol(Symbol::CurlRight) => depth -= 1,
+                Token::Symbol(Symbol::CurlLeft) => depth += 1,
+                Token::Symbol(Symbol::Semicolon) if depth == 0 => {
+                    self.tokenizer.advance();
+                    return;
+                }
+                _ => {}
+            }
+            self.tokenizer.advance();
+        }
+    }
+
+    /// Consumes the current token if it is the given symbol.
+    fn eat_symbol(&mut self, symbol: Symbol) -> bool {
+        let is_symbol = self.is_symbol(symbol);
+        if is_symbol {
+            self.tokenizer.advance();
+        }
+        is_symbol
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokens::Span;
+
+    /// Returns the span of the `n`th token (from 0) of the source with the given text.
+    fn token_span(source: &str, text: &str, n: usize) -> Span {
+        let tokenizer = JackTokenizer::from_source(source.to_string()).unwrap();
+        let tokens = tokenizer.tokens().iter();
+        let token = tokens.filter(|t| t.token.to_string() == text).nth(n);
+        token.unwrap().span.clone()
+    }
+
+    #[test]
+    fn test_parse_class() {
+        // Given
+        let input = r"
+        class Point {
+            field int x, y;
+            static Point origin;
+
+            method int getX() { return x; }
+
+            function void reset(Point p, int value) {
+                var int i;
+                let i = -value;
+                if (~(i < 0)) { do p.move(i, i + 1); } else { let origin[i] = null; }
+                while (true) { do Output.println(); }
+                return;
+            }
+        }";
+
+        // When
+        let class = parse_class(input).unwrap();
+
+        // Then
+        let id = |name: &str, n: usize| Identifier {
+            name: name.to_string(),
+            span: token_span(input, name, n),
+        };
+        let var = |name: &str, n: usize| Expression {
+            term: Term::VarName(id(name, n)),
+            ops: vec![],
+            span: token_span(input, name, n),
+        };
+        let between = |(first, n): (&str, usize), (last, m): (&str, usize)| {
+            token_span(input, first, n).to(&token_span(input, last, m))
+        };
+        pretty_assertions::assert_eq!(
+            class,
+            Class {
+                name: id("Point", 0),
+                var_decs: vec![
+                    ClassVarDec {
+                        kind: ClassVarKind::Field,
+                        ty: Type::Int,
+                        names: vec![id("x", 0), id("y", 0)],
+                    },
+                    ClassVarDec {
+                        kind: ClassVarKind::Static,
+                        ty: Type::Class(String::from("Point")),
+                        names: vec![id("origin", 0)],
+                    },
+                ],
+                subroutines: vec![
+                    SubroutineDec {
+                        kind: SubroutineKind::Method,
+                        return_type: Some(Type::Int),
+                        name: id("getX", 0),
+                        parameters: vec![],
+                        body: SubroutineBody {
+                            var_decs: vec![],
+                            statements: vec![Statement::Return(
+                                Some(var("x", 1)),
+                                between(("return", 0), (";", 2)),
+                            )],
+                        },
+                    },
+                    SubroutineDec {
+                        kind: SubroutineKind::Function,
+                        return_type: None,
+                        name: id("reset", 0),
+                        parameters: vec![
+                            Parameter {
+                                ty: Type::Class(String::from("Point")),
+                                name: id("p", 0),
+                            },
+                            Parameter {
+                                ty: Type::Int,
+                                name: id("value", 0),
+                            },
+                        ],
+                        body: SubroutineBody {
+                            var_decs: vec![VarDec {
+                                ty: Type::Int,
+                                names: vec![id("i", 0)],
+                            }],
+                            statements: vec![
+                                Statement::Let {
+                                    name: id("i", 1),
+                                    index: None,
+                                    value: Expression {
+                                        term: Term::Unary(
+                                            UnaryOp::Neg,
+                                            Box::new(Term::VarName(id("value", 1))),
+                                            between(("-", 0), ("value", 1))
+                                        ),
+                                        ops: vec![],
+                                        span: between(("-", 0), ("value", 1)),
+                                    },
+                                    span: between(("let", 0), (";", 4)),
+                                },
+                                Statement::If {
+                                    condition: Expression {
+                                        term: Term::Unary(
+                                            UnaryOp::Not,
+                                            Box::new(Term::Parenthesized(
+                                                Box::new(Expression {
+                                                    term: Term::VarName(id("i", 2)),
+                                                    ops: vec![(
+                                                        BinaryOp::LessThan,
+                                                        Term::IntConst(
+                                                            0,
+                                                            token_span(input, "0", 0)
+                                                        )
+                                                    )],
+                                                    span: between(("i", 2), ("0", 0)),
+                                                }),
+                                                between(("(", 3), (")", 2))
+                                            )),
+                                            between(("~", 0), (")", 2))
+                                        ),
+                                        ops: vec![],
+                                        span: between(("~", 0), (")", 2)),
+                                    },
+                                    statements: vec![Statement::Do(
+                                        SubroutineCall {
+                                            receiver: Some(id("p", 1)),
+                                            name: id("move", 0),
+                                            arguments: vec![
+                                                var("i", 3),
+                                                Expression {
+                                                    term: Term::VarName(id("i", 4)),
+                                                    ops: vec![(
+                                                        BinaryOp::Add,
+                                                        Term::IntConst(
+                                                            1,
+                                                            token_span(input, "1", 0)
+                                                        )
+                                                    )],
+                                                    span: between(("i", 4), ("1", 0)),
+                                                },
+                                            ],
+                                        },
+                                        between(("do", 0), (";", 5))
+                                    )],
+                                    else_statements: Some(vec![Statement::Let {
+                                        name: id("origin", 1),
+                                        index: Some(Box::new(var("i", 5))),
+                                        value: Expression {
+                                            term: Term::KeywordConst(
+                                                KeywordConst::Null,
+                                                token_span(input, "null", 0)
+                                            ),
+                                            ops: vec![],
+                                            span: token_span(input, "null", 0),
+                                        },
+                                        span: between(("let", 1), (";", 6)),
+                                    }]),
+                                    span: between(("if", 0), ("}", 2)),
+                                },
+                                Statement::While {
+                                    condition: Expression {
+                                        term: Term::KeywordConst(
+                                            KeywordConst::True,
+                                            token_span(input, "true", 0)
+                                        ),
+                                        ops: vec![],
+                                        span: token_span(input, "true", 0),
+                                    },
+                                    statements: vec![Statement::Do(
+                                        SubroutineCall {
+                                            receiver: Some(id("Output", 0)),
+                                            name: id("println", 0),
+                                            arguments: vec![],
+                                        },
+                                        between(("do", 1), (";", 7))
+                                    )],
+                                    span: between(("while", 0), ("}", 3)),
+                                },
+                                Statement::Return(None, between(("return", 1), (";", 8))),
+                            ],
+                        },
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_test_data() {
+        for path in [
+            "test_data/ArrayTest/Main.jack",
+            "test_data/Square/Main.jack",
+            "test_data/Square/Square.jack",
+            "test_data/Square/SquareGame.jack",
+        ] {
+            // Given
+            let source = std::fs::read_to_string(path).expect("failed to read file");
+
+            // When
+            let class = parse_class(&source);
+
+            // Then
+            assert!(class.is_ok(), "{}: {:?}", path, class);
+        }
+    }
+
+    #[test]
+    fn test_parse_class_unexpected_token() {
+        // Given
+        let input = "class Main { function void main() { let x = 1 } }";
+
+        // When
+        let error = parse_class(input).unwrap_err();
+
+        // Then
+        assert_eq!(
+            error,
+            ParseError::UnexpectedToken {
+                expected: String::from("`;`"),
+                found: Token::Symbol(Symbol::CurlRight),
+                span: Span {
+                    file: None,
+                    start: 46,
+                    end: 47,
+                    line: 1,
+                    column: 47,
+                },
+            }
+        );
+        assert_eq!(error.to_string(), "1:47: expected `;`, found `}`");
+    }
+
+    #[test]
+    fn test_parse_class_unexpected_end_of_input() {
+        // Given
+        let input = "class Main { function void main() { let";
+
+        // When
+        let error = parse_class(input).unwrap_err();
+
+        // Then
+        assert_eq!(
+            error,
+            ParseError::UnexpectedEndOfInput {
+                expected: String::from("an identifier"),
+                span: Span {
+                    file: None,
+                    start: 39,
+                    end: 39,
+                    line: 1,
+                    column: 40,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_class_unclosed_delimiter() {
+        // Given
+        let input = "class Main { function void main() { if (x) { } }";
+
+        // When
+        let error = parse_class(input).unwrap_err();
+
+        // Then
+        assert_eq!(
+            error,
+            ParseError::UnclosedDelimiter {
+                expected: String::from("`}`"),
+                found: None,
+                span: Span {
+                    file: None,
+                    start: 48,
+                    end: 48,
+                    line: 1,
+                    column: 49,
+                },
+                delimiter: Symbol::CurlLeft,
+                opening: Box::new(Span {
+                    file: None,
+                    start: 11,
+                    end: 12,
+                    line: 1,
+                    column: 12,
+                }),
+            }
+        );
+        assert_eq!(error.to_string(), "1:49: expected `}`, found end of input");
+    }
+
+    /// Parses the file with each replacement applied once, to break it.
+    fn parse_broken(path: &str, replacements: &[(&str, &str)]) -> (Option<Class>, Vec<ParseError>) {
+        let mut source = std::fs::read_to_string(path).expect("failed to read file");
+        for (from, to) in replacements {
+            assert!(source.contains(from), "{}: {}", path, from);
+            source = source.replacen(from, to, 1);
+        }
+        Parser::new(JackTokenizer::from_source(source).unwrap()).parse_recovering()
+    }
+
+    fn messages(errors: &[ParseError]) -> Vec<(usize, String)> {
+        errors
+            .iter()
+            .map(|e| (e.span().unwrap().line, e.message()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_recovering_statements() {
+        // Given
+        let replacements = [
+            ("let direction = 0;", "let direction = 0"),
+            ("do Sys.wait(5);", "do Sys.wait(5;"),
+            ("let exit = true;", "let exit = ;"),
+        ];
+
+        // When
+        let (class, errors) = parse_broken("test_data/Square/SquareGame.jack", &replacements);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            messages(&errors),
+            vec![
+                (28, String::from("expected `;`, found `return`")),
+                (44, String::from("expected `)`, found `;`")),
+                (60, String::from("expected a term, found `;`")),
+            ]
+        );
+        let class = class.unwrap();
+        let names: Vec<_> = class
+            .subroutines
+            .iter()
+            .map(|s| s.name.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["new", "dispose", "moveSquare", "run"]);
+        let statements = &class.subroutines[0].body.statements;
+        assert!(matches!(
+            statements.as_slice(),
+            [
+                Statement::Let { .. },
+                Statement::Error(_),
+                Statement::Return(..)
+            ]
+        ));
+        let statements = &class.subroutines[2].body.statements;
+        assert!(matches!(
+            statements.as_slice(),
+            [.., Statement::Error(_), Statement::Return(None, _)]
+        ));
+    }
+
+    #[test]
+    fn test_parse_recovering_members() {
+        // Given
+        let replacements = [
+            ("field int size;", "field int size"),
+            ("method void dispose() {", "method void dispose( {"),
+            ("let size = size + 2;", "let size + 2;"),
+        ];
+
+        // When
+        let (class, errors) = parse_broken("test_data/Square/Square.jack", &replacements);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            messages(&errors),
+            vec![
+                (15, String::from("expected `;`, found `constructor`")),
+                (24, String::from("expected a type, found `{`")),
+                (47, String::from("expected `=`, found `+`")),
+            ]
+        );
+        let class = class.unwrap();
+        assert_eq!(class.var_decs.len(), 1);
+        let names: Vec<_> = class
+            .subroutines
+            .iter()
+            .map(|s| s.name.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "new",
+                "draw",
+                "erase",
+                "incSize",
+                "decSize",
+                "moveUp",
+                "moveDown",
+                "moveLeft",
+                "moveRight"
+            ]
+        );
+        match &class.subroutines[3].body.statements[0] {
+            Statement::If { statements, .. } => assert!(matches!(
+                statements.as_slice(),
+                [Statement::Do(..), Statement::Error(_), Statement::Do(..)]
+            )),
+            statement => panic!("expected an if statement, found {:?}", statement),
+        }
+    }
+
+    #[test]
+    fn test_parse_recovering_declarations() {
+        // Given
+        let replacements = [
+            ("var int i, j;", "var int i j;"),
+            ("let i = i | j;", "i = i | j;"),
+            (
+                "    return;\n    }\n}",
+                "    return;\n    }\n    field int x;\n}",
+            ),
+        ];
+
+        // When
+        let (class, errors) = parse_broken("test_data/Square/Main.jack", &replacements);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            messages(&errors),
+            vec![
+                (21, String::from("expected `;`, found `j`")),
+                (32, String::from("expected a statement, found `i`")),
+                (36, String::from("expected `}`, found `field`")),
+            ]
+        );
+        let body = &class.unwrap().subroutines[1].body;
+        assert_eq!(body.var_decs.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_recovering_class_header() {
+        // Given
+        let tokenizer = JackTokenizer::from_source(String::from("class { }")).unwrap();
+
+        // When
+        let (class, errors) = Parser::new(tokenizer).parse_recovering();
+
+        // Then
+        assert_eq!(class, None);
+        assert_eq!(
+            messages(&errors),
+            vec![(1, String::from("expected an identifier, found `{`"))]
+        );
+    }
+}
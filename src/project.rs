@@ -0,0 +1,271 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use walkdir::WalkDir;
+
+/// The .jack files of a directory, which form a program and are compiled
+/// together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompilationUnit {
+    /// The path given on the command line the unit was found under, or the
+    /// directory of the file if a file was given
+    pub root: PathBuf,
+    /// The directory holding the files
+    pub dir: PathBuf,
+    /// The files of the unit, sorted by name
+    pub files: Vec<PathBuf>,
+    /// The other .jack files of the directory when only some of its files
+    /// were given, sorted by name. Their classes can be called by the files
+    /// of the unit, but they aren't processed themselves
+    pub context: Vec<PathBuf>,
+    /// The path of the output directory of the unit below `--out-dir`: the
+    /// path of its directory below its root, or below the common ancestor of
+    /// the roots when there are several, so that the units of different roots
+    /// don't write to the same place
+    pub mirror: PathBuf,
+}
+
+impl CompilationUnit {
+    /// Returns the directory the output of the unit is written to: the
+    /// directory of its sources, or its mirror under `out_dir`.
+    pub fn output_dir(&self, out_dir: Option<&Path>) -> PathBuf {
+        match out_dir {
+            Some(out_dir) => out_dir.join(&self.mirror),
+            None => self.dir.clone(),
+        }
+    }
+}
+
+/// Finds the .jack files under the paths, searching directories recursively
+/// and skipping hidden ones, and groups them by directory. A file given
+/// directly is part of the unit of its directory, whose other files are the
+/// context of the unit.
+pub fn discover(paths: &[PathBuf]) -> Result<Vec<CompilationUnit>, walkdir::Error> {
+    let mut units: BTreeMap<PathBuf, CompilationUnit> = BTreeMap::new();
+    for path in paths {
+        let walker = WalkDir::new(path)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| entry.depth() == 0 || !is_hidden(entry.path()));
+        for entry in walker {
+            let entry = entry?;
+            if !entry.file_type().is_file() || !is_jack_file(entry.path()) {
+                continue;
+            }
+
+            let file = entry.path().to_path_buf();
+            let dir = file.parent().map(Path::to_path_buf).unwrap_or_default();
+            let root = match entry.depth() {
+                0 => dir.clone(),
+                _ => path.clone(),
+            };
+            let unit = units.entry(dir.clone()).or_insert_with(|| CompilationUnit {
+                root,
+                dir,
+                files: Vec::new(),
+                context: Vec::new(),
+                mirror: PathBuf::new(),
+            });
+            if !unit.files.contains(&file) {
+                unit.files.push(file);
+            }
+        }
+    }
+
+    let mut units: Vec<_> = units.into_values().collect();
+    let mut roots: Vec<_> = units.iter().map(|unit| &unit.root).collect();
+    roots.sort();
+    roots.dedup();
+    let ancestor = match roots.as_slice() {
+        [_] => None,
+        roots => common_ancestor(roots.iter().map(|root| canonicalize(root))),
+    };
+    for unit in &mut units {
+        unit.files.sort();
+        unit.context = siblings(&unit.dir)?
+            .into_iter()
+            .filter(|file| !unit.files.contains(file))
+            .collect();
+        unit.mirror = match &ancestor {
+            Some(ancestor) => canonicalize(&unit.dir)
+                .strip_prefix(ancestor)
+                .map(Path::to_path_buf),
+            None => unit.dir.strip_prefix(&unit.root).map(Path::to_path_buf),
+        }
+        .unwrap_or_default();
+    }
+    Ok(units)
+}
+
+/// Returns the longest path all the paths start with.
+fn common_ancestor(mut paths: impl Iterator<Item = PathBuf>) -> Option<PathBuf> {
+    let mut ancestor = paths.next()?;
+    for path in paths {
+        while !path.starts_with(&ancestor) {
+            ancestor.pop();
+        }
+    }
+    Some(ancestor)
+}
+
+/// Returns the absolute path of the directory, which exists since its files
+/// were found.
+fn canonicalize(dir: &Path) -> PathBuf {
+    let dir = current_if_empty(dir);
+    dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
+}
+
+/// Returns the .jack files directly in the directory, sorted by name. The
+/// directory of a bare file name is empty and stands for the current one.
+fn siblings(dir: &Path) -> Result<Vec<PathBuf>, walkdir::Error> {
+    let mut files = Vec::new();
+    let walker = WalkDir::new(current_if_empty(dir))
+        .min_depth(1)
+        .max_depth(1)
+        .sort_by_file_name();
+    for entry in walker {
+        let entry = entry?;
+        if entry.file_type().is_file() && is_jack_file(entry.path()) && !is_hidden(entry.path()) {
+            // Joined to `dir` to match the paths of the files of the unit
+            files.push(dir.join(entry.file_name()));
+        }
+    }
+    Ok(files)
+}
+
+fn current_if_empty(dir: &Path) -> &Path {
+    match dir.as_os_str().is_empty() {
+        true => Path::new("."),
+        false => dir,
+    }
+}
+
+fn is_jack_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext == "jack")
+        .unwrap_or_default()
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy().starts_with('.'))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover() {
+        // Given
+        let paths = vec![PathBuf::from("test_data")];
+
+        // When
+        let units = discover(&paths).unwrap();
+
+        // Then
+        let dirs: Vec<_> = units.iter().map(|unit| unit.dir.clone()).collect();
+        pretty_assertions::assert_eq!(
+            dirs,
+            vec![
+                PathBuf::from("test_data/ArrayTest"),
+                PathBuf::from("test_data/ExpressionLessSquare"),
+                PathBuf::from("test_data/Square"),
+            ]
+        );
+        pretty_assertions::assert_eq!(
+            units[2].files,
+            vec![
+                PathBuf::from("test_data/Square/Main.jack"),
+                PathBuf::from("test_data/Square/Square.jack"),
+                PathBuf::from("test_data/Square/SquareGame.jack"),
+            ]
+        );
+        assert!(units.iter().all(|unit| unit.root == paths[0]));
+        assert!(units.iter().all(|unit| unit.context.is_empty()));
+    }
+
+    #[test]
+    fn test_discover_files() {
+        // Given
+        let paths = vec![
+            PathBuf::from("test_data/Square/Square.jack"),
+            PathBuf::from("test_data/Square/Main.jack"),
+            PathBuf::from("test_data/Square/Main.jack"),
+            PathBuf::from("test_data/ArrayTest/Main.jack"),
+        ];
+
+        // When
+        let units = discover(&paths).unwrap();
+
+        // Then
+        assert_eq!(units.len(), 2);
+        pretty_assertions::assert_eq!(
+            units[1],
+            CompilationUnit {
+                root: PathBuf::from("test_data/Square"),
+                dir: PathBuf::from("test_data/Square"),
+                files: vec![
+                    PathBuf::from("test_data/Square/Main.jack"),
+                    PathBuf::from("test_data/Square/Square.jack"),
+                ],
+                context: vec![PathBuf::from("test_data/Square/SquareGame.jack")],
+                mirror: PathBuf::from("Square"),
+            }
+        );
+        assert_eq!(units[0].context, Vec::<PathBuf>::new());
+    }
+
+    #[test]
+    fn test_discover_missing_path() {
+        assert!(discover(&[PathBuf::from("test_data/Missing")]).is_err());
+    }
+
+    #[test]
+    fn test_output_dir() {
+        // Given
+        let units = discover(&[PathBuf::from("test_data")]).unwrap();
+        let file_unit = discover(&[PathBuf::from("test_data/Square/Main.jack")]).unwrap();
+
+        // When
+        let beside = units[2].output_dir(None);
+        let mirrored = units[2].output_dir(Some(Path::new("out")));
+        let flat = file_unit[0].output_dir(Some(Path::new("out")));
+
+        // Then
+        assert_eq!(beside, PathBuf::from("test_data/Square"));
+        assert_eq!(mirrored, PathBuf::from("out/Square"));
+        assert_eq!(flat, PathBuf::from("out"));
+    }
+
+    #[test]
+    fn test_output_dir_roots() {
+        // Given
+        let dirs = discover(&[
+            PathBuf::from("test_data/Square"),
+            PathBuf::from("test_data/ArrayTest"),
+        ])
+        .unwrap();
+        let files = discover(&[
+            PathBuf::from("test_data/Square/Main.jack"),
+            PathBuf::from("test_data/ArrayTest/Main.jack"),
+        ])
+        .unwrap();
+
+        // When
+        let output_dirs = |units: &[CompilationUnit]| -> Vec<_> {
+            units
+                .iter()
+                .map(|unit| unit.output_dir(Some(Path::new("out"))))
+                .collect()
+        };
+
+        // Then
+        let expected = vec![PathBuf::from("out/ArrayTest"), PathBuf::from("out/Square")];
+        assert_eq!(output_dirs(&dirs), expected);
+        assert_eq!(output_dirs(&files), expected);
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::{Class, ClassVarKind, SubroutineDec, SubroutineKind, Type},
+    tokens::Keyword,
+};
+
+/// The kind of a variable, which determines its scope and storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    /// A class variable shared by all the instances (class scope)
+    Static,
+    /// An instance variable (class scope)
+    Field,
+    /// A subroutine argument (subroutine scope)
+    Arg,
+    /// A subroutine local variable (subroutine scope)
+    Var,
+}
+
+impl Kind {
+    /// Returns the kind declared by the `static`, `field` or `var` keyword.
+    pub fn from_keyword(keyword: &Keyword) -> Option<Self> {
+        match keyword {
+            Keyword::Static => Some(Self::Static),
+            Keyword::Field => Some(Self::Field),
+            Keyword::Var => Some(Self::Var),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Self::Static => "static",
+            Self::Field => "field",
+            Self::Arg => "arg",
+            Self::Var => "var",
+        }
+    }
+
+    fn is_class_scope(&self) -> bool {
+        matches!(self, Self::Static | Self::Field)
+    }
+}
+
+impl From<ClassVarKind> for Kind {
+    fn from(value: ClassVarKind) -> Self {
+        match value {
+            ClassVarKind::Static => Self::Static,
+            ClassVarKind::Field => Self::Field,
+        }
+    }
+}
+
+/// A variable defined in the symbol table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolEntry {
+    pub ty: Type,
+    pub kind: Kind,
+    /// The running index of the variable among the variables of the same kind
+    pub index: u16,
+}
+
+/// Associates the variable names of a class with their type, kind and index.
+/// The class scope holds the static and field variables, the subroutine
+/// scope holds the arguments and local variables of the current subroutine
+/// and shadows the class scope.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    /// The name of the class, used as the type of `this`
+    class_name: String,
+    /// The static and field variables
+    class_scope: HashMap<String, SymbolEntry>,
+    /// The arguments and local variables of the current subroutine
+    subroutine_scope: HashMap<String, SymbolEntry>,
+    /// The number of variables defined so far for each kind
+    counts: HashMap<Kind, u16>,
+}
+
+impl SymbolTable {
+    pub fn new(class_name: &str) -> Self {
+        Self {
+            class_name: class_name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Builds the table of a class, with its static and field variables defined.
+    pub fn from_class(class: &Class) -> Self {
+        let mut table = Self::new(&class.name.name);
+        for var_dec in &class.var_decs {
+            for name in &var_dec.names {
+                table.define(&name.name, var_dec.ty.clone(), var_dec.kind.into());
+            }
+        }
+        table
+    }
+
+    /// Builds the table of a class with the scope of one of its subroutines started.
+    /// Returns `None` if the class has no subroutine with this name.
+    pub fn for_subroutine(class: &Class, subroutine_name: &str) -> Option<Self> {
+        let subroutine = class
+            .subroutines
+            .iter()
+            .find(|s| s.name.name == subroutine_name)?;
+        let mut table = Self::from_class(class);
+        table.enter_subroutine(subroutine);
+        Some(table)
+    }
+
+    /// Starts a new subroutine scope, dropping the previous arguments and variables.
+    pub fn start_subroutine(&mut self) {
+        self.subroutine_scope.clear();
+        self.counts.remove(&Kind::Arg);
+        self.counts.remove(&Kind::Var);
+    }
+
+    /// Starts the scope of the subroutine and defines its arguments and local
+    /// variables. Methods receive the object they operate on as the implicit
+    /// `this` argument 0.
+    pub fn enter_subroutine(&mut self, subroutine: &SubroutineDec) {
+        self.start_subroutine();
+        if subroutine.kind == SubroutineKind::Method {
+            let ty = Type::Class(self.class_name.clone());
+            self.define(Keyword::This.to_str(), ty, Kind::Arg);
+        }
+        for parameter in &subroutine.parameters {
+            self.define(&parameter.name.name, parameter.ty.clone(), Kind::Arg);
+        }
+        for var_dec in &subroutine.body.var_decs {
+            for name in &var_dec.names {
+                self.define(&name.name, var_dec.ty.clone(), Kind::Var);
+            }
+        }
+    }
+
+    /// Defines a new variable and assigns it the next index of its kind.
+    /// A name already declared in the scope keeps its first declaration,
+    /// the checker reporting the duplicate.
+    pub fn define(&mut self, name: &str, ty: Type, kind: Kind) {
+        let scope = match kind.is_class_scope() {
+            true => &mut self.class_scope,
+            false => &mut self.subroutine_scope,
+        };
+        if scope.contains_key(name) {
+            return;
+        }
+
+        let count = self.counts.entry(kind).or_default();
+        let entry = SymbolEntry {
+            ty,
+            kind,
+            index: *count,
+        };
+        *count += 1;
+        scope.insert(name.to_string(), entry);
+    }
+
+    /// Returns the number of variables of the given kind in the current scope.
+    pub fn var_count(&self, kind: Kind) -> u16 {
+        self.counts.get(&kind).copied().unwrap_or_default()
+    }
+
+    /// Looks up a variable, the subroutine scope shadowing the class scope.
+    pub fn lookup(&self, name: &str) -> Option<&SymbolEntry> {
+        self.subroutine_scope
+            .get(name)
+            .or_else(|| self.class_scope.get(name))
+    }
+
+    pub fn kind_of(&self, name: &str) -> Option<Kind> {
+        self.lookup(name).map(|e| e.kind)
+    }
+
+    pub fn type_of(&self, name: &str) -> Option<&Type> {
+        self.lookup(name).map(|e| &e.ty)
+    }
+
+    pub fn index_of(&self, name: &str) -> Option<u16> {
+        self.lookup(name).map(|e| e.index)
+    }
+
+    pub fn class_name(&self) -> &str {
+        &self.class_name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_class;
+
+    fn square_game() -> Class {
+        let source = std::fs::read_to_string("test_data/Square/SquareGame.jack")
+            .expect("failed to read file");
+        parse_class(&source).unwrap()
+    }
+
+    #[test]
+    fn test_for_subroutine() {
+        // Given
+        let class = square_game();
+
+        // When
+        let table = SymbolTable::for_subroutine(&class, "moveSquare").unwrap();
+
+        // Then
+        assert_eq!(
+            table.lookup("square"),
+            Some(&SymbolEntry {
+                ty: Type::Class(String::from("Square")),
+                kind: Kind::Field,
+                index: 0,
+            })
+        );
+        assert_eq!(
+            table.lookup("direction"),
+            Some(&SymbolEntry {
+                ty: Type::Int,
+                kind: Kind::Field,
+                index: 1,
+            })
+        );
+        assert_eq!(
+            table.lookup("this"),
+            Some(&SymbolEntry {
+                ty: Type::Class(String::from("SquareGame")),
+                kind: Kind::Arg,
+                index: 0,
+            })
+        );
+        assert_eq!(table.var_count(Kind::Field), 2);
+        assert!(SymbolTable::for_subroutine(&class, "jump").is_none());
+    }
+
+    #[test]
+    fn test_enter_subroutine() {
+        // Given
+        let class = parse_class(
+            r"
+            class Main {
+                static int count;
+                field int x;
+                method void set(int x, boolean flag) { var char c, d; return; }
+                function void reset(int value) { return; }
+            }",
+        )
+        .unwrap();
+        let mut table = SymbolTable::from_class(&class);
+
+        // When
+        table.enter_subroutine(&class.subroutines[0]);
+
+        // Then
+        assert_eq!(table.kind_of("x"), Some(Kind::Arg));
+        assert_eq!(table.index_of("x"), Some(1));
+        assert_eq!(table.index_of("flag"), Some(2));
+        assert_eq!(table.type_of("d"), Some(&Type::Char));
+        assert_eq!(table.index_of("d"), Some(1));
+        assert_eq!(table.var_count(Kind::Arg), 3);
+        assert_eq!(table.var_count(Kind::Var), 2);
+
+        // When
+        table.enter_subroutine(&class.subroutines[1]);
+
+        // Then
+        assert_eq!(table.kind_of("x"), Some(Kind::Field));
+        assert_eq!(table.index_of("value"), Some(0));
+        assert_eq!(table.lookup("this"), None);
+        assert_eq!(table.lookup("c"), None);
+        assert_eq!(table.kind_of("count"), Some(Kind::Static));
+        assert_eq!(table.var_count(Kind::Var), 0);
+    }
+
+    #[test]
+    fn test_define_duplicate() {
+        // Given
+        let mut table = SymbolTable::new("Main");
+
+        // When
+        table.define("x", Type::Int, Kind::Var);
+        table.define("x", Type::Boolean, Kind::Var);
+        table.define("y", Type::Int, Kind::Var);
+
+        // Then
+        assert_eq!(table.type_of("x"), Some(&Type::Int));
+        assert_eq!(table.index_of("y"), Some(1));
+        assert_eq!(table.var_count(Kind::Var), 2);
+    }
+
+    #[test]
+    fn test_kind_from_keyword() {
+        assert_eq!(Kind::from_keyword(&Keyword::Static), Some(Kind::Static));
+        assert_eq!(Kind::from_keyword(&Keyword::Field), Some(Kind::Field));
+        assert_eq!(Kind::from_keyword(&Keyword::Var), Some(Kind::Var));
+        assert_eq!(Kind::from_keyword(&Keyword::Int), None);
+    }
+}
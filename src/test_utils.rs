@@ -0,0 +1,70 @@
+use crate::{
+    ast::Class,
+    checker::{check_class, Program},
+    control_flow::check_control_flow,
+    errors::{ControlFlowError, SemanticError, TypeError},
+    parser::parse_class,
+    tokens::Span,
+    type_checker::check_types,
+};
+
+/// An error of a check, pointing at the source.
+pub trait Located {
+    fn span(&self) -> &Span;
+    fn message(&self) -> String;
+}
+
+macro_rules! impl_located {
+    ($($error:ty),*) => {
+        $(impl Located for $error {
+            fn span(&self) -> &Span {
+                <$error>::span(self)
+            }
+
+            fn message(&self) -> String {
+                <$error>::message(self)
+            }
+        })*
+    };
+}
+
+impl_located!(SemanticError, TypeError, ControlFlowError);
+
+/// Runs the check on the first class against the program of all the
+/// classes, and returns the line, column and message of its errors.
+pub fn check_sources<E: Located>(
+    sources: &[&str],
+    check: impl Fn(&Class, &Program) -> Vec<E>,
+) -> Vec<(usize, usize, String)> {
+    let classes: Vec<_> = sources.iter().map(|s| parse_class(s).unwrap()).collect();
+    let program = Program::new(&classes);
+    check(&classes[0], &program)
+        .iter()
+        .map(|e| (e.span().line, e.span().column, e.message()))
+        .collect()
+}
+
+#[test]
+fn test_checks_test_data() {
+    for dir in ["test_data/ArrayTest", "test_data/Square"] {
+        // Given
+        let sources: Vec<_> = std::fs::read_dir(dir)
+            .expect("failed to read directory")
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "jack"))
+            .map(|path| std::fs::read_to_string(path).expect("failed to read file"))
+            .collect();
+        let classes: Vec<_> = sources.iter().map(|s| parse_class(s).unwrap()).collect();
+
+        // When
+        let program = Program::new(&classes);
+
+        // Then
+        for class in &classes {
+            let name = &class.name.name;
+            assert_eq!(check_class(class, &program), vec![], "{}", name);
+            assert_eq!(check_types(class, &program), vec![], "{}", name);
+            assert_eq!(check_control_flow(class), vec![], "{}", name);
+        }
+    }
+}
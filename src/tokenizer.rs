@@ -1,164 +1,367 @@
-use std::{path::PathBuf, rc::Rc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use crate::{
-    tokens::{Keyword, Symbol, Token},
-    utils::{
-        remove_comments, replace_carriage_returns_with_single_space,
-        replace_line_breaks_with_single_space, replace_multi_spaces_with_single_space,
-        replace_tabs_with_single_space,
-    },
+    errors::{LexError, ParseError},
+    json::JsonTokens,
+    lexer::Lexer,
+    tokens::{Keyword, Span, SpannedToken, Symbol, Token, Trivia},
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct JackTokenizer {
     /// The input tokenized
-    tokens: Vec<Rc<Token>>,
-    /// The current token being processed.
-    current_token: Option<Rc<Token>>,
+    tokens: Vec<SpannedToken<'static>>,
     /// The current token index
     current_token_index: usize,
-    /// The next token to be processed (since jack is LL1, we only need one lookahead token)
-    next_token: Option<Rc<Token>>,
+    /// The span right after the last token, used to locate the end of the input
+    end_span: Span,
+    /// The whitespace and comments preceding each token, followed by the ones
+    /// ending the input. Only recorded by the lossless constructors.
+    trivia: Vec<Vec<Trivia<'static>>>,
+    /// The source code, only kept by the lossless constructors
+    source: Option<String>,
 }
 
 impl JackTokenizer {
-    pub fn new(path: PathBuf) -> Self {
-        let content = std::fs::read_to_string(path).expect("failed to read file");
-        let clean_content = Self::clean_input(content);
-
-        let tokens: Vec<_> = Self::into_tokens(clean_content)
-            .into_iter()
-            .map(Rc::new)
-            .collect();
-        let current_token = tokens.first().cloned();
-        let next_token = tokens.get(1).cloned();
-
-        Self {
+    pub fn new(path: PathBuf) -> Result<Self, LexError> {
+        let content = Self::read(&path)?;
+        Self::tokenize(&content, Some(Arc::from(path.as_path())))
+    }
+
+    /// Tokenizes Jack source code held in memory.
+    pub fn from_source(content: String) -> Result<Self, LexError> {
+        Self::tokenize(&content, None)
+    }
+
+    /// Tokenizes the file keeping the whitespace and comments, so that
+    /// the source can be reproduced exactly from the tokens.
+    pub fn new_lossless(path: PathBuf) -> Result<Self, LexError> {
+        let content = Self::read(&path)?;
+        Self::tokenize_lossless(content, Some(Arc::from(path.as_path())))
+    }
+
+    /// Tokenizes Jack source code held in memory, keeping the whitespace and comments.
+    pub fn from_source_lossless(content: String) -> Result<Self, LexError> {
+        Self::tokenize_lossless(content, None)
+    }
+
+    fn read(path: &Path) -> Result<String, LexError> {
+        std::fs::read_to_string(path).map_err(|e| LexError::Io {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })
+    }
+
+    /// Collects the tokens read by the lexer, copying their text
+    /// so that the tokenizer doesn't borrow the source.
+    fn tokenize(content: &str, file: Option<Arc<Path>>) -> Result<Self, LexError> {
+        let mut lexer = Lexer::new(content).with_file(file);
+        let tokens = std::iter::from_fn(|| lexer.next_spanned())
+            .map(|token| token.map(SpannedToken::into_owned))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
             tokens,
-            current_token,
             current_token_index: 0,
-            next_token,
-        }
+            end_span: lexer.end_span(),
+            trivia: Vec::new(),
+            source: None,
+        })
     }
 
-    /// Removes the comments, replaces lines breaks with a single space,
-    /// replaces tabs (\r) with a single space character and finally
-    /// replaces multiple space characters with a single space character
-    fn clean_input(input: String) -> String {
-        let removed_comments = remove_comments(input);
-        let removed_line_breaks = replace_line_breaks_with_single_space(removed_comments);
-        let removed_tabs = replace_tabs_with_single_space(removed_line_breaks);
-        let removed_carriage = replace_carriage_returns_with_single_space(removed_tabs);
-        replace_multi_spaces_with_single_space(removed_carriage)
-    }
-
-    /// Converts the input to a stream of tokens
-    /// This is done by iterating the characters
-    /// of the input code and handling 3 cases:
-    /// 1. char is a symbol or a space: we check
-    ///   if the acc string contains a keyword,
-    ///   a digit or a identifier.
-    /// 2. char is a quote: we take the chars up
-    ///   until we reach the next quote.
-    /// 3. char is alphanumeric: we accumulate it
-    ///   into a string until point 1. is reached.
-    fn into_tokens(input: String) -> Vec<Token> {
+    /// Collects the tokens along with the trivia preceding each of them.
+    fn tokenize_lossless(content: String, file: Option<Arc<Path>>) -> Result<Self, LexError> {
+        let mut lexer = Lexer::new(&content).with_file(file);
         let mut tokens = Vec::new();
-        let mut acc = String::new();
-        let mut i = 0;
-
-        let chars: Vec<_> = input.chars().collect();
-
-        while i < chars.len() {
-            let c = chars[i];
-            let is_symbol = Symbol::is_symbol(&c);
-            // If there is a space or a symbol, we check acc
-            if c == ' ' || is_symbol {
-                if !acc.is_empty() {
-                    if Keyword::is_keyword(&acc) {
-                        tokens.push(Token::Keyword(acc.clone().into()));
-                    } else if let Ok(u) = str::parse::<u16>(&acc) {
-                        tokens.push(Token::IntConst(u))
-                    } else {
-                        tokens.push(Token::Identifier(acc.clone()))
-                    }
-                    acc.clear();
-                }
-                if is_symbol {
-                    tokens.push(Token::Symbol(c.into()));
-                }
-                i += 1;
-            }
-            if c == '"' {
-                let string_constant: String = chars[i + 1..chars.len()]
-                    .iter()
-                    .take_while(|c| **c != '"')
-                    .collect();
-                i += string_constant.len() + 2; // we skip the 2 quotes
-                tokens.push(Token::StringConst(string_constant));
-            }
-            // If c is a digit, letter or _, it can be a identifier,
-            // keyword or digit
-            if c.is_alphanumeric() || c == '_' {
-                acc.push(c);
-                i += 1;
+        let mut trivia = Vec::new();
+        loop {
+            trivia.push(
+                lexer
+                    .leading_trivia()?
+                    .into_iter()
+                    .map(Trivia::into_owned)
+                    .collect(),
+            );
+            match lexer.next_spanned() {
+                Some(token) => tokens.push(token?.into_owned()),
+                None => break,
             }
         }
+        let end_span = lexer.end_span();
 
-        tokens
+        Ok(Self {
+            tokens,
+            current_token_index: 0,
+            end_span,
+            trivia,
+            source: Some(content),
+        })
+    }
+
+    /// Returns the `<tokens>` XML of all the tokens, one element per line.
+    pub fn to_xml(&self) -> String {
+        let mut acc = String::from("<tokens>\n");
+        for token in &self.tokens {
+            acc += &(token.token.to_xml_element() + "\n");
+        }
+        acc + "</tokens>\n"
+    }
+
+    /// Returns the tokens as JSON, following the schema of the `json` module.
+    pub fn to_json(&self) -> String {
+        JsonTokens::new(&self.tokens).to_json()
+    }
+
+    /// Returns all the tokens, whatever the current one.
+    pub fn tokens(&self) -> &[SpannedToken<'static>] {
+        &self.tokens
     }
 
     pub fn has_more_tokens(&self) -> bool {
-        self.current_token.is_some()
+        self.current_token_index < self.tokens.len()
     }
 
+    /// Moves to the next token, staying at the end of the input once reached.
     pub fn advance(&mut self) {
-        self.current_token = self.next_token.take();
-        self.next_token = self.tokens.get(self.current_token_index + 2).cloned();
-        self.current_token_index += 1;
+        if self.has_more_tokens() {
+            self.current_token_index += 1;
+        }
     }
 
-    pub fn current_token(&self) -> Rc<Token> {
-        self.current_token
-            .as_ref()
-            .expect("no current token")
-            .clone()
+    /// Returns the current token.
+    /// Panics if all the tokens were processed.
+    pub fn current_token(&self) -> &Token<'static> {
+        self.peek(0).expect("no current token")
     }
 
-    pub fn keyword(&self) -> Keyword {
-        match &*self.current_token() {
-            Token::Keyword(k) => k.clone(),
-            _ => panic!("current token isn't a keyword"),
+    /// Returns the whitespace and comments preceding the current token, or
+    /// the ones ending the input if all the tokens were processed.
+    /// Always empty unless the tokenizer is lossless.
+    pub fn leading_trivia(&self) -> &[Trivia<'static>] {
+        self.trivia
+            .get(self.current_token_index)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Returns the current token as written in the source, which
+    /// differs from its `Display` for integers with leading zeros.
+    /// Panics if all the tokens were processed.
+    pub fn current_text(&self) -> String {
+        let token = self.peek_spanned(0).expect("no current token");
+        match &self.source {
+            Some(source) => source[token.span.start..token.span.end].to_string(),
+            None => token.token.to_string(),
         }
     }
 
-    pub fn symbol(&self) -> Symbol {
-        match &*self.current_token() {
-            Token::Symbol(s) => s.clone(),
-            _ => panic!("current token isn't a symbol"),
+    /// Returns the location of the current token, or the end of
+    /// the input if all the tokens were processed.
+    pub fn current_span(&self) -> Span {
+        self.peek_spanned(0)
+            .map(|t| t.span.clone())
+            .unwrap_or_else(|| self.end_span.clone())
+    }
+
+    /// Returns the span from the start of `start` to the end of the last
+    /// token consumed, e.g. to locate a construct once parsed.
+    pub fn span_from(&self, start: &Span) -> Span {
+        match self.current_token_index.checked_sub(1) {
+            Some(last) => start.to(&self.tokens[last].span),
+            None => start.clone(),
         }
     }
 
-    pub fn identifier(&self) -> String {
-        match &*self.current_token() {
-            Token::Identifier(s) => s.clone(),
-            _ => panic!("current token isn't a identifier"),
+    /// Returns the token `n` positions after the current one
+    /// (0 being the current token), if any.
+    pub fn peek(&self, n: usize) -> Option<&Token<'static>> {
+        self.peek_spanned(n).map(|t| &t.token)
+    }
+
+    /// Returns the token `n` positions after the current one along with its location.
+    pub fn peek_spanned(&self, n: usize) -> Option<&SpannedToken<'static>> {
+        self.tokens.get(self.current_token_index + n)
+    }
+
+    /// Consumes and returns the current token, whatever it is.
+    /// `expected` describes what the caller is looking for in the error
+    /// raised at the end of the input.
+    pub fn take(&mut self, expected: &str) -> Result<SpannedToken<'static>, ParseError> {
+        let token =
+            self.peek_spanned(0)
+                .cloned()
+                .ok_or_else(|| ParseError::UnexpectedEndOfInput {
+                    expected: expected.to_string(),
+                    span: self.current_span(),
+                })?;
+        self.advance();
+        Ok(token)
+    }
+
+    /// Consumes the current token if it is the given one and returns its location.
+    /// A missing closing delimiter is reported along with the delimiter it
+    /// should close.
+    pub fn expect(&mut self, token: Token<'static>) -> Result<Span, ParseError> {
+        let expected = format!("`{}`", token);
+        let error = match self.take(&expected) {
+            Ok(found) if found.token == token => return Ok(found.span),
+            Ok(found) => self.rewind_unexpected(&expected, found),
+            Err(e) => e,
+        };
+        Err(match token {
+            Token::Symbol(closing) => self.unclosed(&closing, error),
+            _ => error,
+        })
+    }
+
+    /// Consumes the current token if it is one of the given keywords.
+    pub fn expect_keyword(&mut self, keywords: &[Keyword]) -> Result<Keyword, ParseError> {
+        let expected = one_of(keywords.iter().map(Keyword::to_str));
+        let found = self.take(&expected)?;
+        match &found.token {
+            Token::Keyword(k) if keywords.contains(k) => Ok(k.clone()),
+            _ => Err(self.rewind_unexpected(&expected, found)),
         }
     }
 
-    pub fn int_val(&self) -> u16 {
-        match &*self.current_token() {
-            Token::IntConst(i) => *i,
-            _ => panic!("current token isn't a int value"),
+    /// Consumes the current token if it is an identifier and returns its name.
+    pub fn expect_identifier(&mut self) -> Result<String, ParseError> {
+        let expected = "an identifier";
+        let found = self.take(expected)?;
+        match &found.token {
+            Token::Identifier(name) => Ok(name.to_string()),
+            _ => Err(self.rewind_unexpected(expected, found)),
         }
     }
 
-    pub fn string_val(&self) -> String {
-        match &*self.current_token() {
-            Token::StringConst(s) => s.clone(),
-            _ => panic!("current token isn't a string value"),
+    /// Returns the current position in the tokens, to rewind to later.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.current_token_index)
+    }
+
+    /// Moves back (or forward) to a position returned by `checkpoint`.
+    pub fn rewind(&mut self, checkpoint: Checkpoint) {
+        self.current_token_index = checkpoint.0;
+    }
+
+    pub fn keyword(&self) -> Result<Keyword, ParseError> {
+        match self.current("a keyword")? {
+            Token::Keyword(k) => Ok(k.clone()),
+            _ => Err(self.unexpected("a keyword")),
+        }
+    }
+
+    pub fn symbol(&self) -> Result<Symbol, ParseError> {
+        match self.current("a symbol")? {
+            Token::Symbol(s) => Ok(s.clone()),
+            _ => Err(self.unexpected("a symbol")),
+        }
+    }
+
+    pub fn identifier(&self) -> Result<String, ParseError> {
+        match self.current("an identifier")? {
+            Token::Identifier(s) => Ok(s.to_string()),
+            _ => Err(self.unexpected("an identifier")),
+        }
+    }
+
+    pub fn int_val(&self) -> Result<u16, ParseError> {
+        match self.current("an integer constant")? {
+            Token::IntConst(i) => Ok(*i),
+            _ => Err(self.unexpected("an integer constant")),
+        }
+    }
+
+    pub fn string_val(&self) -> Result<String, ParseError> {
+        match self.current("a string constant")? {
+            Token::StringConst(s) => Ok(s.to_string()),
+            _ => Err(self.unexpected("a string constant")),
+        }
+    }
+
+    /// Returns the current token, or an error at the end of the input.
+    fn current(&self, expected: &str) -> Result<&Token<'static>, ParseError> {
+        self.peek(0).ok_or_else(|| self.unexpected(expected))
+    }
+
+    /// Returns the error for the current token not being the expected one.
+    pub fn unexpected(&self, expected: &str) -> ParseError {
+        match self.peek_spanned(0) {
+            Some(found) => ParseError::unexpected(expected, found),
+            None => ParseError::UnexpectedEndOfInput {
+                expected: expected.to_string(),
+                span: self.end_span.clone(),
+            },
+        }
+    }
+
+    /// Turns the error for a missing closing delimiter into an
+    /// `UnclosedDelimiter` if the matching opening one precedes it.
+    fn unclosed(&self, closing: &Symbol, error: ParseError) -> ParseError {
+        let Some(delimiter) = closing.opening_delimiter() else {
+            return error;
+        };
+        let mut depth = 0;
+        let opening = self.tokens[..self.current_token_index]
+            .iter()
+            .rev()
+            .find(|t| match &t.token {
+                Token::Symbol(s) if s == closing => {
+                    depth += 1;
+                    false
+                }
+                Token::Symbol(s) if *s == delimiter => match depth {
+                    0 => true,
+                    _ => {
+                        depth -= 1;
+                        false
+                    }
+                },
+                _ => false,
+            });
+        let Some(opening) = opening else {
+            return error;
+        };
+
+        let (expected, found, span) = match error {
+            ParseError::UnexpectedToken {
+                expected,
+                found,
+                span,
+            } => (expected, Some(found), span),
+            ParseError::UnexpectedEndOfInput { expected, span } => (expected, None, span),
+            error => return error,
+        };
+        ParseError::UnclosedDelimiter {
+            expected,
+            found,
+            span,
+            delimiter,
+            opening: Box::new(opening.span.clone()),
         }
     }
+
+    /// Puts back the token just taken and returns the error for it.
+    fn rewind_unexpected(&mut self, expected: &str, found: SpannedToken<'static>) -> ParseError {
+        self.current_token_index -= 1;
+        ParseError::unexpected(expected, &found)
+    }
+}
+
+/// A position in the tokens of a `JackTokenizer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint(usize);
+
+/// Describes a list of alternatives (e.g. "one of `let`, `do`").
+pub(crate) fn one_of<'a>(names: impl Iterator<Item = &'a str>) -> String {
+    let names: Vec<_> = names.map(|n| format!("`{}`", n)).collect();
+    match names.len() {
+        1 => names[0].clone(),
+        _ => format!("one of {}", names.join(", ")),
+    }
 }
 
 #[cfg(test)]
@@ -166,163 +369,254 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_clean_input() {
+    fn test_advance() {
         // Given
-        let lines = r"// File name: projects/10/Square/SquareGame.jack
-
-        // (same as projects/9/Square/SquareGame.jack)
-        /**
-         * Implements the Square game.
-         * This simple game allows the user to move a black square around
-         * the screen, and change the square's size during the movement.
-         * When the game starts, a square of 30 by 30 pixels is shown at the
-         * top-left corner of the screen. The user controls the square as follows.
-         * The 4 arrow keys are used to move the square up, down, left, and right.
-         * The 'z' and 'x' keys are used, respectively, to decrement and increment
-         * the square's size. The 'q' key is used to quit the game.
-         */
-        class SquareGame {
-           field Square square; // the square of this game
-           field int direction; // the square's current direction: 
-                                // 0=none, 1=up, 2=down, 3=left, 4=right"
-            .to_string();
+        let mut tokenizer =
+            JackTokenizer::new(PathBuf::from("test_data/Square/SquareGame.jack")).unwrap();
 
         // When
-        let lines_without_comments = JackTokenizer::clean_input(lines);
+        tokenizer.advance();
 
         // Then
+        assert_eq!(tokenizer.current_token_index, 1);
         assert_eq!(
-            lines_without_comments,
-            "class SquareGame { field Square square; field int direction;"
-        )
+            tokenizer.current_token(),
+            &Token::Identifier("SquareGame".into())
+        );
+        assert_eq!(tokenizer.peek(1), Some(&Token::Symbol(Symbol::CurlLeft)));
+    }
+
+    fn tokenizer(input: &str) -> JackTokenizer {
+        JackTokenizer::from_source(input.to_string()).unwrap()
     }
 
     #[test]
-    fn test_into_tokens() {
+    fn test_peek() {
         // Given
-        let input = r"/**
-        * Implements the Square game.
-        * This simple game allows the user to move a black square around
-        * the screen, and change the square's size during the movement.
-        * When the game starts, a square of 30 by 30 pixels is shown at the
-        * top-left corner of the screen. The user controls the square as follows.
-        * The 4 arrow keys are used to move the square up, down, left, and right.
-        * The 'z' and 'x' keys are used, respectively, to decrement and increment
-        * the square's size. The 'q' key is used to quit the game.
-        */
-       class SquareGame {
-          field Square square; // the square of this game
-          field int direction; // the square's current direction: 
-                               // 0=none, 1=up, 2=down, 3=left, 4=right
-       
-          /** Constructs a new Square Game. */
-          constructor SquareGame new() {
-             // Creates a 30 by 30 pixels square and positions it at the top-left
-             // of the screen.
-             let square = Square.new(0, 0, 30);
-             let direction = 0;  // initial state is no movement
-             return this;
-          }"
-        .to_string();
+        let mut tokenizer = tokenizer("foo.bar(x)");
 
         // When
-        let cleaned_input = JackTokenizer::clean_input(input);
-        let tokens = JackTokenizer::into_tokens(cleaned_input);
+        tokenizer.advance();
 
         // Then
-        pretty_assertions::assert_eq!(
-            tokens,
-            vec![
-                Token::Keyword(Keyword::Class),
-                Token::Identifier(String::from("SquareGame")),
-                Token::Symbol(Symbol::CurlLeft),
-                Token::Keyword(Keyword::Field),
-                Token::Identifier(String::from("Square")),
-                Token::Identifier(String::from("square")),
-                Token::Symbol(Symbol::Semicolon),
-                Token::Keyword(Keyword::Field),
-                Token::Keyword(Keyword::Int),
-                Token::Identifier(String::from("direction")),
-                Token::Symbol(Symbol::Semicolon),
-                Token::Keyword(Keyword::Constructor),
-                Token::Identifier(String::from("SquareGame")),
-                Token::Identifier(String::from("new")),
-                Token::Symbol(Symbol::ParenthesisLeft),
-                Token::Symbol(Symbol::ParenthesisRight),
-                Token::Symbol(Symbol::CurlLeft),
-                Token::Keyword(Keyword::Let),
-                Token::Identifier(String::from("square")),
-                Token::Symbol(Symbol::Equal),
-                Token::Identifier(String::from("Square")),
-                Token::Symbol(Symbol::Dot),
-                Token::Identifier(String::from("new")),
-                Token::Symbol(Symbol::ParenthesisLeft),
-                Token::IntConst(0),
-                Token::Symbol(Symbol::Comma),
-                Token::IntConst(0),
-                Token::Symbol(Symbol::Comma),
-                Token::IntConst(30),
-                Token::Symbol(Symbol::ParenthesisRight),
-                Token::Symbol(Symbol::Semicolon),
-                Token::Keyword(Keyword::Let),
-                Token::Identifier(String::from("direction")),
-                Token::Symbol(Symbol::Equal),
-                Token::IntConst(0),
-                Token::Symbol(Symbol::Semicolon),
-                Token::Keyword(Keyword::Return),
-                Token::Keyword(Keyword::This),
-                Token::Symbol(Symbol::Semicolon),
-                Token::Symbol(Symbol::CurlRight),
-            ]
+        assert_eq!(tokenizer.peek(0), Some(&Token::Symbol(Symbol::Dot)));
+        assert_eq!(
+            tokenizer.peek(2),
+            Some(&Token::Symbol(Symbol::ParenthesisLeft))
         );
+        assert_eq!(tokenizer.peek(5), None);
+        assert_eq!(tokenizer.peek_spanned(1).unwrap().span.column, 5);
     }
 
     #[test]
-    fn test_into_tokens_complex() {
+    fn test_advance_past_end() {
         // Given
-        let input = r#"
-            let length = Keyboard.readInt("HOW MANY NUMBERS? ");"#
-            .to_string();
+        let mut tokenizer = tokenizer("x");
 
         // When
-        let cleaned_input = JackTokenizer::clean_input(input);
-        let tokens = JackTokenizer::into_tokens(cleaned_input);
+        tokenizer.advance();
+        tokenizer.advance();
 
         // Then
-        pretty_assertions::assert_eq!(
-            tokens,
-            vec![
-                Token::Keyword(Keyword::Let),
-                Token::Identifier(String::from("length")),
-                Token::Symbol(Symbol::Equal),
-                Token::Identifier(String::from("Keyboard")),
-                Token::Symbol(Symbol::Dot),
-                Token::Identifier(String::from("readInt")),
-                Token::Symbol(Symbol::ParenthesisLeft),
-                Token::StringConst(String::from("HOW MANY NUMBERS? ")),
-                Token::Symbol(Symbol::ParenthesisRight),
-                Token::Symbol(Symbol::Semicolon),
-            ]
+        assert!(!tokenizer.has_more_tokens());
+        assert_eq!(tokenizer.checkpoint(), Checkpoint(1));
+    }
+
+    #[test]
+    fn test_expect() {
+        // Given
+        let mut tokenizer = tokenizer("let x;");
+
+        // When
+        let keyword = tokenizer.expect_keyword(&[Keyword::Do, Keyword::Let]);
+        let name = tokenizer.expect_identifier();
+        let semicolon = tokenizer.expect(Token::Symbol(Symbol::Semicolon));
+
+        // Then
+        assert_eq!(keyword, Ok(Keyword::Let));
+        assert_eq!(name, Ok(String::from("x")));
+        assert_eq!(semicolon.map(|span| span.column), Ok(6));
+        assert!(!tokenizer.has_more_tokens());
+    }
+
+    #[test]
+    fn test_expect_unexpected_token() {
+        // Given
+        let mut tokenizer = tokenizer("do x;");
+
+        // When
+        let keyword = tokenizer.expect_keyword(&[Keyword::Let, Keyword::While]);
+        let symbol = tokenizer.expect(Token::Symbol(Symbol::Semicolon));
+        let identifier = tokenizer.identifier();
+
+        // Then
+        assert_eq!(
+            keyword.unwrap_err().to_string(),
+            "1:1: expected one of `let`, `while`, found `do`"
+        );
+        assert_eq!(
+            symbol.unwrap_err().to_string(),
+            "1:1: expected `;`, found `do`"
+        );
+        assert_eq!(
+            identifier.unwrap_err(),
+            ParseError::UnexpectedToken {
+                expected: String::from("an identifier"),
+                found: Token::Keyword(Keyword::Do),
+                span: Span {
+                    file: None,
+                    start: 0,
+                    end: 2,
+                    line: 1,
+                    column: 1,
+                },
+            }
         );
+        assert_eq!(tokenizer.keyword(), Ok(Keyword::Do));
     }
 
     #[test]
-    fn test_advance() {
+    fn test_expect_end_of_input() {
         // Given
-        let mut tokenizer = JackTokenizer::new(PathBuf::from("test_data/Square/SquareGame.jack"));
+        let mut tokenizer = tokenizer("x ");
 
         // When
         tokenizer.advance();
+        let error = tokenizer.expect_identifier().unwrap_err();
 
         // Then
-        assert_eq!(tokenizer.current_token_index, 1);
         assert_eq!(
-            &*tokenizer.current_token.unwrap(),
-            &Token::Identifier(String::from("SquareGame"))
+            error,
+            ParseError::UnexpectedEndOfInput {
+                expected: String::from("an identifier"),
+                span: Span {
+                    file: None,
+                    start: 2,
+                    end: 2,
+                    line: 1,
+                    column: 3,
+                },
+            }
+        );
+        assert!(tokenizer.int_val().is_err());
+    }
+
+    #[test]
+    fn test_checkpoint_rewind() {
+        // Given
+        let mut tokenizer = tokenizer("a[1]");
+        let checkpoint = tokenizer.checkpoint();
+
+        // When
+        tokenizer.expect_identifier().unwrap();
+        tokenizer
+            .expect(Token::Symbol(Symbol::SquareBracketLeft))
+            .unwrap();
+        let value = tokenizer.int_val();
+        tokenizer.rewind(checkpoint);
+
+        // Then
+        assert_eq!(value, Ok(1));
+        assert_eq!(tokenizer.identifier(), Ok(String::from("a")));
+    }
+
+    #[test]
+    fn test_new_records_file() {
+        // Given
+        let path = PathBuf::from("test_data/Square/Main.jack");
+
+        // When
+        let tokenizer = JackTokenizer::new(path.clone()).unwrap();
+
+        // Then
+        let span = tokenizer.current_span();
+        assert_eq!(span.file.as_deref(), Some(path.as_path()));
+        assert_eq!((span.line, span.column), (9, 1));
+        assert_eq!(tokenizer.current_token(), &Token::Keyword(Keyword::Class));
+    }
+
+    fn lex_error(input: &str) -> LexError {
+        JackTokenizer::from_source(input.to_string()).unwrap_err()
+    }
+
+    fn error_span(start: usize, end: usize, column: usize) -> Span {
+        Span {
+            file: None,
+            start,
+            end,
+            line: 1,
+            column,
+        }
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        assert_eq!(
+            lex_error(r#"let s = "abc;"#),
+            LexError::UnterminatedString {
+                span: error_span(8, 13, 9)
+            }
+        );
+    }
+
+    #[test]
+    fn test_newline_in_string() {
+        assert_eq!(
+            lex_error("let s = \"ab\nc\";"),
+            LexError::NewlineInString {
+                span: error_span(8, 11, 9)
+            }
+        );
+    }
+
+    #[test]
+    fn test_illegal_character() {
+        assert_eq!(
+            lex_error("let x = y @ 2;"),
+            LexError::IllegalCharacter {
+                character: '@',
+                span: error_span(10, 11, 11)
+            }
+        );
+        assert_eq!(
+            lex_error("#"),
+            LexError::IllegalCharacter {
+                character: '#',
+                span: error_span(0, 1, 1)
+            }
         );
+    }
+
+    #[test]
+    fn test_integer_out_of_range() {
         assert_eq!(
-            &*tokenizer.next_token.unwrap(),
-            &Token::Symbol(Symbol::CurlLeft)
+            lex_error("let x = 70000;"),
+            LexError::IntegerOutOfRange {
+                value: String::from("70000"),
+                span: error_span(8, 13, 9)
+            }
         );
     }
+
+    #[test]
+    fn test_unterminated_block_comment() {
+        assert_eq!(
+            lex_error("let x = 1; /* never closed"),
+            LexError::UnterminatedBlockComment {
+                span: error_span(11, 26, 12)
+            }
+        );
+    }
+
+    #[test]
+    fn test_missing_file() {
+        // Given
+        let path = PathBuf::from("test_data/Missing.jack");
+
+        // When
+        let error = JackTokenizer::new(path.clone()).unwrap_err();
+
+        // Then
+        assert!(matches!(error, LexError::Io { path: p, .. } if p == path));
+    }
 }
@@ -1,18 +1,39 @@
+use std::{borrow::Cow, fmt};
+
+mod span;
+mod trivia;
+
+pub use span::{Span, SpannedToken};
+pub use trivia::{Trivia, TriviaKind};
+
+/// A token of the Jack language. Identifiers and string constants
+/// borrow their text from the source when read by the lexer.
 #[derive(Debug, PartialEq, Clone)]
-pub enum Token {
+pub enum Token<'src> {
     /// A keyword in the Jack language (e.g. class, method, function, etc.)
     Keyword(Keyword),
     /// A symbol in the Jack language (e.g. {, }, (, ), etc.)
     Symbol(Symbol),
     /// An identifier in the Jack language (e.g. x, foo, bar, etc.)
-    Identifier(String),
+    Identifier(Cow<'src, str>),
     /// An integer constant in the Jack language (e.g. 123, 456, etc.)
     IntConst(u16),
     /// A string constant in the Jack language (e.g. "Hello, World!", "foo", etc.)
-    StringConst(String),
+    StringConst(Cow<'src, str>),
 }
 
-impl Token {
+impl Token<'_> {
+    /// Copies the borrowed text so that the token outlives the source.
+    pub fn into_owned(self) -> Token<'static> {
+        match self {
+            Self::Keyword(k) => Token::Keyword(k),
+            Self::Symbol(s) => Token::Symbol(s),
+            Self::Identifier(i) => Token::Identifier(Cow::Owned(i.into_owned())),
+            Self::IntConst(i) => Token::IntConst(i),
+            Self::StringConst(s) => Token::StringConst(Cow::Owned(s.into_owned())),
+        }
+    }
+
     pub fn start_xml(&self) -> String {
         match self {
             Self::Keyword(_) => "<keyword>",
@@ -24,6 +45,17 @@ impl Token {
         .to_string()
     }
 
+    /// Returns the opening tag carrying the given attributes
+    /// (e.g. `<identifier category="var">`).
+    pub fn start_xml_with_attributes(&self, attributes: &[(&str, String)]) -> String {
+        let start = self.start_xml();
+        let attributes: String = attributes
+            .iter()
+            .map(|(name, value)| format!(" {}=\"{}\"", name, value))
+            .collect();
+        format!("{}{}>", start.trim_end_matches('>'), attributes)
+    }
+
     pub fn end_xml(&self) -> String {
         match self {
             Self::Keyword(_) => r"</keyword>",
@@ -39,9 +71,27 @@ impl Token {
         match self {
             Self::Keyword(k) => k.to_str().to_string(),
             Self::Symbol(s) => s.to_xml().to_string(),
-            Self::Identifier(i) => i.clone(),
+            Self::Identifier(i) => i.to_string(),
             Self::IntConst(i) => i.to_string(),
-            Self::StringConst(s) => s.clone(),
+            Self::StringConst(s) => s.to_string(),
+        }
+    }
+
+    /// Returns the token as a single XML element (e.g. `<symbol> { </symbol>`).
+    pub fn to_xml_element(&self) -> String {
+        self.start_xml() + " " + &self.to_xml() + " " + &self.end_xml()
+    }
+}
+
+impl fmt::Display for Token<'_> {
+    /// Writes the token as it appears in the Jack source code.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Keyword(k) => write!(f, "{}", k.to_str()),
+            Self::Symbol(s) => write!(f, "{}", s.to_str()),
+            Self::Identifier(i) => write!(f, "{}", i),
+            Self::IntConst(i) => write!(f, "{}", i),
+            Self::StringConst(s) => write!(f, "\"{}\"", s),
         }
     }
 }
@@ -71,9 +121,12 @@ pub enum Keyword {
     Return,
 }
 
-impl From<String> for Keyword {
-    fn from(value: String) -> Self {
-        match value.as_str() {
+impl<'a> TryFrom<&'a str> for Keyword {
+    type Error = &'a str;
+
+    /// Returns the keyword matching the string, or the string itself if it isn't one.
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        Ok(match value {
             "class" => Self::Class,
             "constructor" => Self::Constructor,
             "function" => Self::Function,
@@ -95,8 +148,8 @@ impl From<String> for Keyword {
             "else" => Self::Else,
             "while" => Self::While,
             "return" => Self::Return,
-            _ => panic!("not a keyword"),
-        }
+            _ => return Err(value),
+        })
     }
 }
 
@@ -175,9 +228,12 @@ pub enum Symbol {
     Tilte,
 }
 
-impl From<char> for Symbol {
-    fn from(value: char) -> Self {
-        match value {
+impl TryFrom<char> for Symbol {
+    type Error = char;
+
+    /// Returns the symbol matching the character, or the character itself if it isn't one.
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        Ok(match value {
             '{' => Self::CurlLeft,
             '}' => Self::CurlRight,
             '(' => Self::ParenthesisLeft,
@@ -197,8 +253,8 @@ impl From<char> for Symbol {
             '>' => Self::MoreThan,
             '=' => Self::Equal,
             '~' => Self::Tilte,
-            _ => panic!("not a symbol"),
-        }
+            _ => return Err(value),
+        })
     }
 }
 
@@ -227,6 +283,16 @@ impl Symbol {
         }
     }
 
+    /// Returns the delimiter opened by the closing one (e.g. `{` for `}`).
+    pub fn opening_delimiter(&self) -> Option<Self> {
+        match self {
+            Self::CurlRight => Some(Self::CurlLeft),
+            Self::ParenthesisRight => Some(Self::ParenthesisLeft),
+            Self::SquareBracketRight => Some(Self::SquareBracketLeft),
+            _ => None,
+        }
+    }
+
     pub fn to_char(&self) -> char {
         self.to_str()
             .chars()
@@ -0,0 +1,55 @@
+use std::{fmt, path::Path, sync::Arc};
+
+use super::Token;
+
+/// The location of a token in its source file.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Span {
+    /// The file the token was read from, if any
+    pub file: Option<Arc<Path>>,
+    /// The byte offset of the first character of the token
+    pub start: usize,
+    /// The byte offset following the last character of the token
+    pub end: usize,
+    /// The line of the first character, starting at 1
+    pub line: usize,
+    /// The column of the first character, counted in characters and starting at 1
+    pub column: usize,
+}
+
+impl Span {
+    /// Returns the span from the start of this one to the end of `end`.
+    pub fn to(&self, end: &Span) -> Span {
+        Span {
+            end: end.end,
+            ..self.clone()
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    /// Writes the span as `file:line:column`, or `line:column` without a file.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file.display())?;
+        }
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A token along with its location in the source file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken<'src> {
+    pub token: Token<'src>,
+    pub span: Span,
+}
+
+impl SpannedToken<'_> {
+    /// Copies the borrowed text so that the token outlives the source.
+    pub fn into_owned(self) -> SpannedToken<'static> {
+        SpannedToken {
+            token: self.token.into_owned(),
+            span: self.span,
+        }
+    }
+}
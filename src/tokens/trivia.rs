@@ -0,0 +1,35 @@
+use std::borrow::Cow;
+
+use super::Span;
+
+/// The kind of source text found between two tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    /// Spaces, tabs and line breaks
+    Whitespace,
+    /// A comment in the form of // up to the end of the line
+    LineComment,
+    /// A comment starting with "/*" and ending with "*/"
+    BlockComment,
+    /// An API comment starting with "/**" and ending with "*/"
+    DocComment,
+}
+
+/// Whitespace or a comment, kept to reproduce the source exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia<'src> {
+    pub kind: TriviaKind,
+    pub text: Cow<'src, str>,
+    pub span: Span,
+}
+
+impl Trivia<'_> {
+    /// Copies the borrowed text so that the trivia outlives the source.
+    pub fn into_owned(self) -> Trivia<'static> {
+        Trivia {
+            kind: self.kind,
+            text: Cow::Owned(self.text.into_owned()),
+            span: self.span,
+        }
+    }
+}
@@ -0,0 +1,537 @@
+use crate::{
+    ast::{
+        BinaryOp, Class, Expression, KeywordConst, Statement, SubroutineCall, SubroutineDec, Term,
+        Type, UnaryOp,
+    },
+    checker::Program,
+    errors::TypeError,
+    symbol_table::SymbolTable,
+    tokens::Span,
+};
+
+/// The name of the class whose instances can be indexed.
+const ARRAY: &str = "Array";
+
+/// The type of an expression, as far as it is known.
+#[derive(Debug, Clone, PartialEq)]
+enum ValueType {
+    Type(Type),
+    /// The `null` constant
+    Null,
+    /// No value, returned by a `void` subroutine
+    Void,
+    Unknown,
+}
+
+impl ValueType {
+    fn name(&self) -> &str {
+        match self {
+            Self::Type(ty) => ty.name(),
+            Self::Null => "null",
+            Self::Void => "void",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(self, Self::Type(Type::Int | Type::Char) | Self::Unknown)
+    }
+
+    fn is_boolean(&self) -> bool {
+        matches!(self, Self::Type(Type::Boolean) | Self::Unknown)
+    }
+
+    /// Whether a value of this type can be stored in a variable of the given type.
+    fn fits(&self, ty: &Type) -> bool {
+        match (self, ty) {
+            (Self::Unknown, _) => true,
+            (Self::Null, Type::Class(_)) => true,
+            (Self::Type(Type::Class(_)), Type::Class(name)) if name == ARRAY => true,
+            (Self::Type(Type::Int | Type::Char), Type::Int | Type::Char) => true,
+            (Self::Type(found), ty) => found == ty,
+            _ => false,
+        }
+    }
+}
+
+/// Checks the types of a class of the program and returns its errors in the
+/// order of the source. Jack doesn't check types, so only the values most
+/// likely to be mistakes are reported: `int` and `char` are interchangeable,
+/// `null` fits any class type, any object fits an `Array`, which the OS uses
+/// as a plain pointer, and the elements of an `Array` and the values of
+/// unknown type fit anywhere.
+pub fn check_types(class: &Class, program: &Program) -> Vec<TypeError> {
+    let mut checker = TypeChecker {
+        class,
+        program,
+        symbol_table: SymbolTable::from_class(class),
+        return_type: None,
+        errors: Vec::new(),
+    };
+    for subroutine in &class.subroutines {
+        checker.check_subroutine(subroutine);
+    }
+    checker.errors
+}
+
+/// Walks the AST of a class, computing the type of each expression.
+#[derive(Debug)]
+struct TypeChecker<'a> {
+    /// The class being checked
+    class: &'a Class,
+    /// The classes the class can call
+    program: &'a Program,
+    /// The variables of the class and of the current subroutine
+    symbol_table: SymbolTable,
+    /// The return type of the current subroutine, `None` if `void`
+    return_type: Option<Type>,
+    errors: Vec<TypeError>,
+}
+
+impl TypeChecker<'_> {
+    fn check_subroutine(&mut self, subroutine: &SubroutineDec) {
+        self.symbol_table.enter_subroutine(subroutine);
+        self.return_type = subroutine.return_type.clone();
+        self.check_statements(&subroutine.body.statements);
+    }
+
+    fn check_statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.check_statement(statement);
+        }
+    }
+
+    fn check_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Let {
+                name, index, value, ..
+            } => {
+                let variable = self.symbol_table.lookup(&name.name).cloned();
+                let value_type = self.check_expression(value);
+                let Some(variable) = variable else {
+                    return;
+                };
+                match index {
+                    Some(index) => {
+                        self.check_array(&name.name, &variable.ty, &name.span);
+                        self.check_index(index);
+                    }
+                    None => self.expect(&variable.ty, &value_type, &value.span),
+                }
+            }
+            Statement::If {
+                condition,
+                statements,
+                else_statements,
+                ..
+            } => {
+                self.check_condition(condition);
+                self.check_statements(statements);
+                if let Some(else_statements) = else_statements {
+                    self.check_statements(else_statements);
+                }
+            }
+            Statement::While {
+                condition,
+                statements,
+                ..
+            } => {
+                self.check_condition(condition);
+                self.check_statements(statements);
+            }
+            Statement::Do(call, _) => {
+                self.check_call(call);
+            }
+            Statement::Return(value, span) => {
+                let value_type = match value {
+                    Some(value) => self.check_expression(value),
+                    None => ValueType::Void,
+                };
+                let span = value.as_ref().map_or(span, |value| &value.span);
+                match (self.return_type.clone(), &value_type) {
+                    (Some(return_type), _) => self.expect(&return_type, &value_type, span),
+                    (None, ValueType::Void) => {}
+                    (None, _) => self.errors.push(TypeError::Mismatch {
+                        expected: ValueType::Void.name().to_string(),
+                        found: value_type.name().to_string(),
+                        span: span.clone(),
+                    }),
+                }
+            }
+            Statement::Error(_) => {}
+        }
+    }
+
+    fn check_condition(&mut self, condition: &Expression) {
+        let condition_type = self.check_expression(condition);
+        self.expect(&Type::Boolean, &condition_type, &condition.span);
+    }
+
+    /// Checks an array index, which must be an integer.
+    fn check_index(&mut self, index: &Expression) {
+        let index_type = self.check_expression(index);
+        self.expect(&Type::Int, &index_type, &index.span);
+    }
+
+    /// Checks that the indexed variable is an `Array`.
+    fn check_array(&mut self, name: &str, ty: &Type, span: &Span) {
+        if *ty != Type::Class(ARRAY.to_string()) {
+            self.errors.push(TypeError::NotAnArray {
+                name: name.to_string(),
+                ty: ty.name().to_string(),
+                span: span.clone(),
+            });
+        }
+    }
+
+    /// Checks that a value of type `found` fits the `expected` type.
+    fn expect(&mut self, expected: &Type, found: &ValueType, span: &Span) {
+        if !found.fits(expected) {
+            self.errors.push(TypeError::Mismatch {
+                expected: expected.name().to_string(),
+                found: found.name().to_string(),
+                span: span.clone(),
+            });
+        }
+    }
+
+    /// Returns the type of the expression. Jack has no operator precedence,
+    /// so the operators are applied from left to right.
+    fn check_expression(&mut self, expression: &Expression) -> ValueType {
+        let mut value_type = self.check_term(&expression.term);
+        let mut span = expression.term.span().clone();
+        for (op, term) in &expression.ops {
+            let term_type = self.check_term(term);
+            let operands = [(&value_type, &span), (&term_type, term.span())];
+            value_type = self.check_binary(*op, &operands);
+            span = span.to(term.span());
+        }
+        value_type
+    }
+
+    /// Checks the operands of a binary operator, along with their locations,
+    /// and returns the type of its result, unknown if an operand is invalid.
+    fn check_binary(&mut self, op: BinaryOp, operands: &[(&ValueType, &Span); 2]) -> ValueType {
+        match op {
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div => {
+                match self.check_operands(op, operands, ValueType::is_numeric, "`int`") {
+                    true => ValueType::Type(Type::Int),
+                    false => ValueType::Unknown,
+                }
+            }
+            BinaryOp::LessThan | BinaryOp::MoreThan => {
+                match self.check_operands(op, operands, ValueType::is_numeric, "`int`") {
+                    true => ValueType::Type(Type::Boolean),
+                    false => ValueType::Unknown,
+                }
+            }
+            // Bitwise on integers and logical on booleans
+            BinaryOp::And | BinaryOp::Or => {
+                let valid = |ty: &ValueType| ty.is_numeric() || ty.is_boolean();
+                if !self.check_operands(op, operands, valid, "`int` or `boolean`") {
+                    return ValueType::Unknown;
+                }
+                match (operands[0].0, operands[1].0) {
+                    (ValueType::Type(Type::Boolean), _) | (_, ValueType::Type(Type::Boolean)) => {
+                        ValueType::Type(Type::Boolean)
+                    }
+                    (ValueType::Unknown, ValueType::Unknown) => ValueType::Unknown,
+                    _ => ValueType::Type(Type::Int),
+                }
+            }
+            BinaryOp::Equal => ValueType::Type(Type::Boolean),
+        }
+    }
+
+    /// Reports the first operand of the operator that isn't `valid`, and
+    /// returns whether they all are.
+    fn check_operands(
+        &mut self,
+        op: BinaryOp,
+        operands: &[(&ValueType, &Span)],
+        valid: impl Fn(&ValueType) -> bool,
+        expected: &str,
+    ) -> bool {
+        match operands.iter().find(|(operand, _)| !valid(operand)) {
+            Some(&(operand, span)) => {
+                self.errors.push(TypeError::InvalidOperand {
+                    operator: op.to_symbol(),
+                    expected: expected.to_string(),
+                    found: operand.name().to_string(),
+                    span: span.clone(),
+                });
+                false
+            }
+            None => true,
+        }
+    }
+
+    /// Returns the type of the term.
+    fn check_term(&mut self, term: &Term) -> ValueType {
+        match term {
+            Term::IntConst(..) => ValueType::Type(Type::Int),
+            Term::StringConst(..) => ValueType::Type(Type::Class(String::from("String"))),
+            Term::KeywordConst(KeywordConst::True | KeywordConst::False, _) => {
+                ValueType::Type(Type::Boolean)
+            }
+            Term::KeywordConst(KeywordConst::Null, _) => ValueType::Null,
+            Term::KeywordConst(KeywordConst::This, _) => {
+                ValueType::Type(Type::Class(self.class.name.name.clone()))
+            }
+            Term::VarName(name) => match self.symbol_table.lookup(&name.name) {
+                Some(variable) => ValueType::Type(variable.ty.clone()),
+                None => ValueType::Unknown,
+            },
+            Term::ArrayAccess(name, index, _) => {
+                if let Some(variable) = self.symbol_table.lookup(&name.name).cloned() {
+                    self.check_array(&name.name, &variable.ty, &name.span);
+                }
+                self.check_index(index);
+                ValueType::Unknown
+            }
+            Term::Call(call, _) => match self.check_call(call) {
+                ValueType::Void => {
+                    self.errors.push(TypeError::VoidValue {
+                        subroutine: self.callee_name(call),
+                        span: call.name.span.clone(),
+                    });
+                    ValueType::Unknown
+                }
+                value_type => value_type,
+            },
+            Term::Parenthesized(expression, _) => self.check_expression(expression),
+            Term::Unary(op, term, _) => {
+                let term_type = self.check_term(term);
+                let (valid, expected) = match op {
+                    UnaryOp::Neg => (term_type.is_numeric(), "`int`"),
+                    UnaryOp::Not => (
+                        term_type.is_numeric() || term_type.is_boolean(),
+                        "`int` or `boolean`",
+                    ),
+                };
+                if !valid {
+                    self.errors.push(TypeError::InvalidOperand {
+                        operator: op.to_symbol(),
+                        expected: expected.to_string(),
+                        found: term_type.name().to_string(),
+                        span: term.span().clone(),
+                    });
+                    return ValueType::Unknown;
+                }
+                match (op, term_type) {
+                    (UnaryOp::Not, ValueType::Type(Type::Boolean)) => {
+                        ValueType::Type(Type::Boolean)
+                    }
+                    (_, ValueType::Unknown) => ValueType::Unknown,
+                    _ => ValueType::Type(Type::Int),
+                }
+            }
+        }
+    }
+
+    /// Checks the arguments of the call against the parameters of the
+    /// subroutine and returns the type of its result.
+    fn check_call(&mut self, call: &SubroutineCall) -> ValueType {
+        let argument_types: Vec<_> = call
+            .arguments
+            .iter()
+            .map(|argument| self.check_expression(argument))
+            .collect();
+        let signature = self
+            .callee_class(call)
+            .and_then(|class| self.program.class(&class))
+            .and_then(|class| class.subroutines.get(&call.name.name))
+            .cloned();
+        let Some(signature) = signature else {
+            return ValueType::Unknown;
+        };
+
+        let arguments = call.arguments.iter().zip(&argument_types);
+        for (parameter, (argument, argument_type)) in signature.parameters.iter().zip(arguments) {
+            self.expect(parameter, argument_type, &argument.span);
+        }
+        match signature.return_type {
+            Some(ty) => ValueType::Type(ty),
+            None => ValueType::Void,
+        }
+    }
+
+    /// Returns the name of the class declaring the called subroutine, if the
+    /// receiver is a variable of a class type or a class.
+    fn callee_class(&self, call: &SubroutineCall) -> Option<String> {
+        match &call.receiver {
+            None => Some(self.class.name.name.clone()),
+            Some(receiver) => match self.symbol_table.lookup(&receiver.name) {
+                Some(variable) => match &variable.ty {
+                    Type::Class(name) => Some(name.clone()),
+                    _ => None,
+                },
+                None => Some(receiver.name.clone()),
+            },
+        }
+    }
+
+    /// Returns the full name of the called subroutine (e.g. `Output.printInt`).
+    fn callee_name(&self, call: &SubroutineCall) -> String {
+        let class = self.callee_class(call).unwrap_or_default();
+        format!("{}.{}", class, call.name.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::check_sources;
+
+    #[test]
+    fn test_check_types_statements() {
+        // Given
+        let source = r#"class Main {
+  function int f(boolean b, char c) {
+    var int i; var Array a; var String s;
+    let i = b;
+    let c = 65;
+    let s = null;
+    let i[0] = 1;
+    let a[b] = s;
+    if (i) { return s; }
+    while (b) { return a[1]; }
+    if (b) { return; }
+    return "f";
+  }
+  method void g() { return 1; }
+}"#;
+
+        // When
+        let errors = check_sources(&[source], check_types);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            errors,
+            vec![
+                (
+                    4,
+                    13,
+                    String::from("mismatched types: expected `int`, found `boolean`")
+                ),
+                (7, 9, String::from("cannot index `i` of type `int`")),
+                (
+                    8,
+                    11,
+                    String::from("mismatched types: expected `int`, found `boolean`")
+                ),
+                (
+                    9,
+                    9,
+                    String::from("mismatched types: expected `boolean`, found `int`")
+                ),
+                (
+                    9,
+                    21,
+                    String::from("mismatched types: expected `int`, found `String`")
+                ),
+                (
+                    11,
+                    14,
+                    String::from("mismatched types: expected `int`, found `void`")
+                ),
+                (
+                    12,
+                    12,
+                    String::from("mismatched types: expected `int`, found `String`")
+                ),
+                (
+                    14,
+                    28,
+                    String::from("mismatched types: expected `void`, found `int`")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_types_expressions() {
+        // Given
+        let source = r#"class Main {
+  method void f(boolean b, Main m) {
+    var int i;
+    let i = (i + 1) * 2;
+    let b = (i < 2) & ~b;
+    let i = i & 3;
+    let i = b + 1;
+    let i = 1 + b;
+    let b = -b;
+    let i = m.f(true, this);
+    do m.f(1, null);
+    return;
+  }
+}"#;
+
+        // When
+        let errors = check_sources(&[source], check_types);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            errors,
+            vec![
+                (
+                    7,
+                    13,
+                    String::from("operator `+` expects `int`, found `boolean`")
+                ),
+                (
+                    8,
+                    17,
+                    String::from("operator `+` expects `int`, found `boolean`")
+                ),
+                (
+                    9,
+                    14,
+                    String::from("operator `-` expects `int`, found `boolean`")
+                ),
+                (10, 15, String::from("`Main.f` returns no value")),
+                (
+                    11,
+                    12,
+                    String::from("mismatched types: expected `boolean`, found `int`")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_types_os_calls() {
+        // Given
+        let source = r#"class Main {
+  method void f() {
+    var boolean b; var Array a;
+    let b = Keyboard.readInt("n");
+    let a = Memory.alloc(2);
+    do Screen.setColor(1);
+    do Memory.deAlloc(this);
+    do Output.printString(Keyboard.readLine("s"));
+    return;
+  }
+}"#;
+
+        // When
+        let errors = check_sources(&[source], check_types);
+
+        // Then
+        pretty_assertions::assert_eq!(
+            errors,
+            vec![
+                (
+                    4,
+                    13,
+                    String::from("mismatched types: expected `boolean`, found `int`")
+                ),
+                (
+                    6,
+                    24,
+                    String::from("mismatched types: expected `boolean`, found `int`")
+                ),
+            ]
+        );
+    }
+}
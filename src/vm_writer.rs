@@ -0,0 +1,116 @@
+/// A memory segment of the Hack virtual machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment {
+    Constant,
+    Argument,
+    Local,
+    Static,
+    This,
+    That,
+    Pointer,
+    Temp,
+}
+
+impl Segment {
+    pub fn to_str(&self) -> &str {
+        match self {
+            Self::Constant => "constant",
+            Self::Argument => "argument",
+            Self::Local => "local",
+            Self::Static => "static",
+            Self::This => "this",
+            Self::That => "that",
+            Self::Pointer => "pointer",
+            Self::Temp => "temp",
+        }
+    }
+}
+
+/// An arithmetic or logical command of the Hack virtual machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Add,
+    Sub,
+    Neg,
+    Eq,
+    Gt,
+    Lt,
+    And,
+    Or,
+    Not,
+}
+
+impl Command {
+    pub fn to_str(&self) -> &str {
+        match self {
+            Self::Add => "add",
+            Self::Sub => "sub",
+            Self::Neg => "neg",
+            Self::Eq => "eq",
+            Self::Gt => "gt",
+            Self::Lt => "lt",
+            Self::And => "and",
+            Self::Or => "or",
+            Self::Not => "not",
+        }
+    }
+}
+
+/// Emits Hack VM commands, one per line.
+#[derive(Debug, Default)]
+pub struct VMWriter {
+    /// The VM code written so far
+    output: String,
+}
+
+impl VMWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write_push(&mut self, segment: Segment, index: u16) {
+        self.write_line(&format!("push {} {}", segment.to_str(), index));
+    }
+
+    pub fn write_pop(&mut self, segment: Segment, index: u16) {
+        self.write_line(&format!("pop {} {}", segment.to_str(), index));
+    }
+
+    pub fn write_arithmetic(&mut self, command: Command) {
+        self.write_line(command.to_str());
+    }
+
+    pub fn write_label(&mut self, label: &str) {
+        self.write_line(&format!("label {}", label));
+    }
+
+    pub fn write_goto(&mut self, label: &str) {
+        self.write_line(&format!("goto {}", label));
+    }
+
+    pub fn write_if(&mut self, label: &str) {
+        self.write_line(&format!("if-goto {}", label));
+    }
+
+    pub fn write_call(&mut self, name: &str, n_args: usize) {
+        self.write_line(&format!("call {} {}", name, n_args));
+    }
+
+    pub fn write_function(&mut self, name: &str, n_locals: usize) {
+        self.write_line(&format!("function {} {}", name, n_locals));
+    }
+
+    pub fn write_return(&mut self) {
+        self.write_line("return");
+    }
+
+    /// Returns the VM code written so far.
+    pub fn into_output(self) -> String {
+        self.output
+    }
+
+    fn write_line(&mut self, line: &str) {
+        self.output += line;
+        self.output += "\n";
+    }
+}
@@ -0,0 +1,100 @@
+use std::process::{Command, Output};
+
+/// Runs the compiler with the arguments.
+fn compiler(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_compiler"))
+        .args(args)
+        .output()
+        .expect("failed to run the compiler")
+}
+
+#[test]
+fn test_check_single_file() {
+    // When
+    let output = compiler(&["check", "--color", "never", "test_data/Square/Main.jack"]);
+
+    // Then
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn test_compile_single_file() {
+    // When
+    let output = compiler(&["compile", "--stdout", "test_data/Square/Main.jack"]);
+
+    // Then
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // Only the given file is compiled, its siblings are only read
+    assert!(stdout.starts_with("function Main.main 1\ncall SquareGame.new 0\n"));
+    assert!(!stdout.contains("function SquareGame.new"));
+}
+
+#[test]
+fn test_bare_file_name() {
+    // When
+    let output = Command::new(env!("CARGO_BIN_EXE_compiler"))
+        .args(["compile", "--stdout", "Main.jack"])
+        .current_dir("test_data/Square")
+        .output()
+        .expect("failed to run the compiler");
+
+    // Then
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // The siblings of the file are found in the current directory
+    assert!(stdout.starts_with("function Main.main 1\ncall SquareGame.new 0\n"));
+    assert!(!stdout.contains("function SquareGame.new"));
+}
+
+#[test]
+fn test_same_output_paths() {
+    // When
+    let out_dir = std::env::temp_dir().join("compiler_test_same_output_paths");
+    let output = compiler(&[
+        "compile",
+        "--out-dir",
+        out_dir.to_str().unwrap(),
+        "test_data/Square",
+        "./test_data/Square",
+    ]);
+
+    // Then
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("would both be written to"), "{}", stderr);
+    assert!(!out_dir.exists());
+}
+
+#[test]
+fn test_check_errors_in_source_order() {
+    // Given
+    let dir = std::env::temp_dir().join("compiler_test_check_errors_in_source_order");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("Main.jack");
+    std::fs::write(
+        &file,
+        "class Main {\n  function int f() { return; }\n  function void g() { do h(); return; }\n}\n",
+    )
+    .unwrap();
+
+    // When
+    let output = compiler(&["check", "--color", "never", file.to_str().unwrap()]);
+
+    // Then
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    let control_flow = stderr.find("error[E0501]").expect(&stderr);
+    let semantic = stderr.find("error[E0302]").expect(&stderr);
+    assert!(control_flow < semantic, "{}", stderr);
+}
@@ -0,0 +1,174 @@
+use std::path::{Path, PathBuf};
+
+use compiler::{
+    compilation_engine::CompilationEngine,
+    formatter,
+    json::{JsonTokens, JsonTree},
+    tokenizer::JackTokenizer,
+};
+use walkdir::WalkDir;
+
+/// Returns every `.jack` file under `test_data`, sorted by path.
+fn jack_files() -> Vec<PathBuf> {
+    let mut files = WalkDir::new("test_data")
+        .into_iter()
+        .filter_map(Result::ok)
+        .map(|entry| entry.into_path())
+        .filter(|path| {
+            path.extension()
+                .map(|ext| ext == "jack")
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+/// Returns the reference file named `{stem}{suffix}.xml` next to the `.jack` file.
+fn reference(jack_file: &Path, suffix: &str) -> String {
+    let stem = jack_file.file_stem().unwrap().to_str().unwrap();
+    let path = jack_file.with_file_name(format!("{}{}.xml", stem, suffix));
+    std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", path.display(), e))
+}
+
+/// Trims each line and drops the blank ones, so that the comparison
+/// ignores indentation, line endings and trailing newlines.
+fn normalize(xml: &str) -> Vec<&str> {
+    xml.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Compares the output to the reference, ignoring whitespace, and panics
+/// with a line by line diff of the two on mismatch.
+fn assert_same_xml(file: &Path, output: &str, expected: &str) {
+    let output = normalize(output).join("\n");
+    let expected = normalize(expected).join("\n");
+    if output != expected {
+        let diff = pretty_assertions::Changeset::new(&expected, &output, "\n");
+        panic!(
+            "{} differs from its reference (red: expected, green: output):\n{}",
+            file.display(),
+            diff
+        );
+    }
+}
+
+#[test]
+fn test_golden_tokens() {
+    let files = jack_files();
+    assert!(!files.is_empty(), "no .jack file found under test_data");
+
+    for file in files {
+        // Given
+        let expected = reference(&file, "T");
+
+        // When
+        let tokenizer = JackTokenizer::new(file.clone())
+            .unwrap_or_else(|e| panic!("{}: {}", file.display(), e));
+
+        // Then
+        assert_same_xml(&file, &tokenizer.to_xml(), &expected);
+    }
+}
+
+#[test]
+fn test_golden_parse_trees() {
+    let files = jack_files();
+    assert!(!files.is_empty(), "no .jack file found under test_data");
+
+    for file in files {
+        // Given
+        let expected = reference(&file, "");
+
+        // When
+        let tokenizer = JackTokenizer::new(file.clone())
+            .unwrap_or_else(|e| panic!("{}: {}", file.display(), e));
+        let output = CompilationEngine::new(tokenizer).compile().unwrap();
+
+        // Then
+        assert_same_xml(&file, &output, &expected);
+    }
+}
+
+#[test]
+fn test_golden_lossless_round_trip() {
+    for file in jack_files() {
+        // Given
+        let source = std::fs::read_to_string(&file).unwrap();
+
+        // When
+        let tokenizer = JackTokenizer::new_lossless(file.clone())
+            .unwrap_or_else(|e| panic!("{}: {}", file.display(), e));
+        let cst = CompilationEngine::new(tokenizer).compile_tree().unwrap();
+
+        // Then
+        assert!(
+            cst.to_source() == source,
+            "{} isn't reproduced",
+            file.display()
+        );
+        assert_same_xml(&file, &cst.to_xml(), &reference(&file, ""));
+    }
+}
+
+#[test]
+fn test_golden_format() {
+    for file in jack_files() {
+        // Given
+        let source = std::fs::read_to_string(&file).unwrap();
+
+        // When
+        let formatted = formatter::format_source(source.clone())
+            .unwrap_or_else(|e| panic!("{}: {}", file.display(), e));
+
+        // Then
+        let reformatted = formatter::format_source(formatted.clone()).unwrap();
+        assert!(
+            reformatted == formatted,
+            "{} isn't idempotent",
+            file.display()
+        );
+        let tokens = JackTokenizer::from_source(formatted).unwrap().to_xml();
+        assert_same_xml(&file, &tokens, &reference(&file, "T"));
+    }
+}
+
+#[test]
+fn test_golden_json_round_trip() {
+    for file in jack_files() {
+        // Given
+        let tokenizer = JackTokenizer::new(file.clone())
+            .unwrap_or_else(|e| panic!("{}: {}", file.display(), e));
+        let cst = CompilationEngine::extended(tokenizer.clone())
+            .compile_tree()
+            .unwrap();
+
+        // When
+        let tokens = JsonTokens::from_json(&tokenizer.to_json()).unwrap();
+        let tree = JsonTree::from_json(&cst.to_json()).unwrap();
+
+        // Then
+        assert_eq!(tokens.file, Some(file.display().to_string()));
+        assert_same_xml(&file, &tokens.to_xml().unwrap(), &reference(&file, "T"));
+        assert!(
+            tree.to_xml().unwrap() == cst.to_xml(),
+            "{} isn't reproduced from JSON",
+            file.display()
+        );
+    }
+}
+
+#[test]
+fn test_normalize() {
+    // Given
+    let xml = "<tokens>\r\n  <keyword> class </keyword>\n\n</tokens>";
+
+    // Then
+    pretty_assertions::assert_eq!(
+        normalize(xml),
+        vec!["<tokens>", "<keyword> class </keyword>", "</tokens>"]
+    );
+}